@@ -2,9 +2,15 @@ use clap::{Parser, Subcommand};
 use std::error::Error;
 
 mod mid2xml;
+mod packadder;
 mod packcreator;
+mod packextractor;
+mod validator;
 pub use self::mid2xml::*;
+pub use self::packadder::*;
 pub use self::packcreator::*;
+pub use self::packextractor::*;
+pub use self::validator::*;
 
 // From Cargo.toml
 const PKG_NAME: &str = env!("CARGO_PKG_NAME");
@@ -25,8 +31,14 @@ struct Options {
 enum SubCommand {
     #[clap(name = "mid2xml", about = "Convert gh/rb mid to guitar praise xml")]
     Mid2Xml(Mid2XmlApp),
+    #[clap(name = "packadd", about = "Add CH songs to existing guitar praise pack")]
+    PackAdder(PackAdderApp),
     #[clap(name = "packcreate", about = "Create guitar praise pack from CH song directory")]
     PackCreator(PackCreatorApp),
+    #[clap(name = "packextract", about = "Extract guitar praise pack to CH song directories")]
+    PackExtractor(PackExtractorApp),
+    #[clap(name = "validate", about = "Check guitar praise pack for missing or broken files")]
+    Validator(ValidatorApp),
 }
 
 #[derive(Debug)]
@@ -44,7 +56,10 @@ impl GPTool {
     pub fn run(&mut self) -> Result<(), Box<dyn Error>> {
         match &mut self.options.commands {
             SubCommand::Mid2Xml(app) => app.process(),
+            SubCommand::PackAdder(app) => app.process(),
             SubCommand::PackCreator(app) => app.process(),
+            SubCommand::PackExtractor(app) => app.process(),
+            SubCommand::Validator(app) => app.process(),
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,54 @@
+use crate::apps::{SubApp};
+use clap::Parser;
+use log::info;
+use praise_mod_lib::pack::*;
+use std::error::Error;
+
+#[derive(Parser, Debug)]
+pub struct PackAdderApp {
+    #[clap(help = "Path to input CH songs directory", required = true)]
+    pub songs_path: String,
+    #[clap(help = "Path to existing song pack directory (i.e. ep04)", required = true)]
+    pub pack_path: String,
+    #[clap(long, default_value = "path", value_parser = ["path", "title", "artist", "album"], help = "Order used when assigning ids to new songs")]
+    pub order: String,
+    #[clap(long, help = "Path to text file listing song paths (one per line) in order used when assigning ids to new songs")]
+    pub manifest: Option<String>,
+    #[clap(long, help = "Re-convert all songs instead of re-using unchanged songs from previous build")]
+    pub no_cache: bool,
+    #[clap(long, help = "Path to write json build report")]
+    pub report: Option<String>,
+}
+
+impl PackAdderApp {
+    fn to_add_ops(&self) -> PackAddOptions {
+        PackAddOptions {
+            songs_path: self.songs_path
+                .to_owned(),
+            pack_path: self.pack_path
+                .to_owned(),
+            no_cache: self.no_cache,
+            order: match (&self.manifest, self.order.as_str()) {
+                (Some(manifest), _) => SongOrder::Manifest(manifest.into()),
+                (None, "title") => SongOrder::Title,
+                (None, "artist") => SongOrder::Artist,
+                (None, "album") => SongOrder::Album,
+                _ => SongOrder::Path,
+            },
+        }
+    }
+}
+
+impl SubApp for PackAdderApp {
+    fn process(&mut self) -> Result<(), Box<dyn Error>> {
+        let ops = self.to_add_ops();
+        let report = add_to_pack(&ops)?;
+
+        if let Some(report_path) = &self.report {
+            report.write_to_file(report_path)?;
+            info!("Wrote build report to \"{}\"", report_path);
+        }
+
+        Ok(())
+    }
+}
@@ -14,6 +14,18 @@ pub struct PackCreatorApp {
     pub name: Option<String>,
     #[clap(long, short, default_value = "4", help = "Numeric id for song pack (must be between 4-98)")]
     pub id: u8,
+    #[clap(long, default_value = "path", value_parser = ["path", "title", "artist", "album"], help = "Order used when assigning ids to new songs")]
+    pub order: String,
+    #[clap(long, help = "Path to text file listing song paths (one per line) in order used when assigning ids to new songs")]
+    pub manifest: Option<String>,
+    #[clap(long, value_parser = ["count", "artist", "folder"], help = "Split songs into multiple packs with sequential ids (by song count, artist initial or top-level folder)")]
+    pub split: Option<String>,
+    #[clap(long, default_value = "500", help = "Max songs per pack when splitting by count")]
+    pub split_size: usize,
+    #[clap(long, help = "Re-convert all songs instead of re-using unchanged songs from previous build")]
+    pub no_cache: bool,
+    #[clap(long, help = "Path to write json build report")]
+    pub report: Option<String>,
 }
 
 impl PackCreatorApp {
@@ -25,7 +37,15 @@ impl PackCreatorApp {
                 .to_owned(),
             name: self.name
                 .to_owned(),
-            id: self.id
+            id: self.id,
+            no_cache: self.no_cache,
+            order: match (&self.manifest, self.order.as_str()) {
+                (Some(manifest), _) => SongOrder::Manifest(manifest.into()),
+                (None, "title") => SongOrder::Title,
+                (None, "artist") => SongOrder::Artist,
+                (None, "album") => SongOrder::Album,
+                _ => SongOrder::Path,
+            },
         }
     }
 }
@@ -33,6 +53,33 @@ impl PackCreatorApp {
 impl SubApp for PackCreatorApp {
     fn process(&mut self) -> Result<(), Box<dyn Error>> {
         let ops = self.to_pack_ops();
-        create_pack(&ops)
+
+        let split = match self.split.as_deref() {
+            Some("count") => Some(PackSplit::Count(self.split_size)),
+            Some("artist") => Some(PackSplit::ArtistInitial),
+            Some("folder") => Some(PackSplit::Folder),
+            _ => None,
+        };
+
+        match split {
+            Some(split) => {
+                let report = create_split_packs(&ops, split)?;
+
+                if let Some(report_path) = &self.report {
+                    report.write_to_file(report_path)?;
+                    info!("Wrote build report to \"{}\"", report_path);
+                }
+            },
+            None => {
+                let report = create_pack(&ops)?;
+
+                if let Some(report_path) = &self.report {
+                    report.write_to_file(report_path)?;
+                    info!("Wrote build report to \"{}\"", report_path);
+                }
+            }
+        }
+
+        Ok(())
     }
 }
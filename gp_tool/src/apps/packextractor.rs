@@ -0,0 +1,34 @@
+use crate::apps::{SubApp};
+use clap::Parser;
+use praise_mod_lib::pack::*;
+use std::error::Error;
+
+#[derive(Parser, Debug)]
+pub struct PackExtractorApp {
+    #[clap(help = "Path to input song pack directory (i.e. ep04)", required = true)]
+    pub pack_path: String,
+    #[clap(help = "Path to output CH songs directory", required = true)]
+    pub output_path: String,
+    #[clap(long, short, help = "Write notes as .mid instead of .chart")]
+    pub midi: bool,
+}
+
+impl PackExtractorApp {
+    fn to_extract_ops(&self) -> PackExtractOptions {
+        PackExtractOptions {
+            pack_path: self.pack_path
+                .to_owned(),
+            output_path: self.output_path
+                .to_owned(),
+            midi: self.midi,
+        }
+    }
+}
+
+impl SubApp for PackExtractorApp {
+    fn process(&mut self) -> Result<(), Box<dyn Error>> {
+        let ops = self.to_extract_ops();
+        extract_pack(&ops)?;
+        Ok(())
+    }
+}
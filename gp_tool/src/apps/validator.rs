@@ -0,0 +1,45 @@
+use crate::apps::{SubApp};
+use clap::Parser;
+use log::{error, info, warn};
+use praise_mod_lib::pack::*;
+use std::error::Error;
+
+#[derive(Parser, Debug)]
+pub struct ValidatorApp {
+    #[clap(help = "Path to song pack directory (i.e. ep04)", required = true)]
+    pub pack_path: String,
+    #[clap(long, help = "Path to write json validation report")]
+    pub report: Option<String>,
+}
+
+impl SubApp for ValidatorApp {
+    fn process(&mut self) -> Result<(), Box<dyn Error>> {
+        let report = validate_pack(&self.pack_path)?;
+
+        for issue in report.issues.iter() {
+            let location = match (issue.song_id, &issue.file_name) {
+                (Some(id), Some(file_name)) => format!("[{:03}] {}: ", id, file_name),
+                (Some(id), None) => format!("[{:03}] ", id),
+                (None, Some(file_name)) => format!("{}: ", file_name),
+                (None, None) => String::new(),
+            };
+
+            match issue.severity {
+                IssueSeverity::Error => error!("{}{}", location, &issue.message),
+                IssueSeverity::Warning => warn!("{}{}", location, &issue.message),
+            }
+        }
+
+        if let Some(report_path) = &self.report {
+            report.write_to_file(report_path)?;
+            info!("Wrote validation report to \"{}\"", report_path);
+        }
+
+        if !report.is_valid() {
+            return Err(format!("Found {} errors and {} warnings in song pack", report.error_count(), report.warning_count()).into());
+        }
+
+        info!("No errors found in {} songs ({} warnings)", report.song_count, report.warning_count());
+        Ok(())
+    }
+}
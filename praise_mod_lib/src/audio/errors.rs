@@ -10,4 +10,24 @@ pub enum AudioReaderError {
     CantDecodeAudioFile {
         text: String,
     },
-}
\ No newline at end of file
+    #[error("Encrypted mogg (version {version}) is not supported")]
+    EncryptedMogg {
+        version: u32,
+    },
+    #[error("Audio format \".{ext}\" is not supported")]
+    UnsupportedAudioFormat {
+        ext: String,
+    },
+}
+
+#[derive(Debug, ThisError)]
+pub enum AudioWriterError {
+    #[error("Can't encode audio because of \"{text}\"")]
+    CantEncodeAudio {
+        text: String,
+    },
+    #[error("Can't write audio file because of \"{text}\"")]
+    CantWriteAudioFile {
+        text: String,
+    },
+}
@@ -1,33 +1,74 @@
 mod encode;
 mod errors;
 mod meta;
+mod opus_reader;
 mod reader;
+mod symphonia_reader;
 mod writer;
 
 pub use self::encode::*;
 pub use self::errors::*;
 pub use self::meta::*;
+pub use self::opus_reader::*;
 pub use self::reader::*;
+pub use self::symphonia_reader::*;
 pub use self::writer::*;
-use std::error::Error;
+use crate::PraiseError;
+use lewton::VorbisError;
+use lewton::inside_ogg::OggStreamReader;
 use std::fs::{copy, create_dir_all, read, write};
+use std::io::Cursor;
 use std::path::{Path, PathBuf};
 
-pub fn ogg_to_dpo(in_path: &Path, out_path: &Path) -> Result<(), Box<dyn Error>> {
+pub fn ogg_to_dpo(in_path: &Path, out_path: &Path) -> Result<(), PraiseError> {
     // Read in bytes
-    let mut data = read(&in_path)?;
+    let data = read(&in_path)?;
+    ogg_bytes_to_dpo(&data, out_path)
+}
 
+pub fn ogg_bytes_to_dpo(data: &[u8], out_path: &Path) -> Result<(), PraiseError> {
     // "Encrypt" audio
-    for b in data.iter_mut() {
-        *b = *b ^ 0x0A;
-    }
+    let data = data
+        .iter()
+        .map(|b| *b ^ 0x0A)
+        .collect::<Vec<u8>>();
 
     // Write to file
     write(&out_path, data)?;
     Ok(())
 }
 
-pub fn copy_ogg_file(in_path: &Path, out_path: &Path) -> Result<(), Box<dyn Error>> {
+pub fn dpo_to_ogg(in_path: &Path, out_path: &Path) -> Result<(), PraiseError> {
+    // Same xor operation both ways
+    ogg_to_dpo(in_path, out_path)
+}
+
+pub fn get_dpo_length_in_ms(dpo_path: &Path) -> Result<f64, PraiseError> {
+    // Read in bytes and "decrypt" audio
+    let mut data = read(&dpo_path)?;
+
+    for b in data.iter_mut() {
+        *b = *b ^ 0x0A;
+    }
+
+    let decode_error = |err: VorbisError| AudioReaderError::CantDecodeAudioFile {
+        text: err.to_string(),
+    };
+
+    // Decode every packet to make sure stream is valid
+    let mut stream = OggStreamReader::new(Cursor::new(data)).map_err(decode_error)?;
+    let channel_count = (stream.ident_hdr.audio_channels as usize).max(1);
+    let sample_rate = stream.ident_hdr.audio_sample_rate;
+
+    let mut sample_count = 0;
+    while let Some(samples) = stream.read_dec_packet_itl().map_err(decode_error)? {
+        sample_count += samples.len() / channel_count;
+    }
+
+    Ok((sample_count as f64 * 1000.0) / sample_rate as f64)
+}
+
+pub fn copy_ogg_file(in_path: &Path, out_path: &Path) -> Result<(), PraiseError> {
     // Copy file
     copy(&in_path, &out_path)?;
     Ok(())
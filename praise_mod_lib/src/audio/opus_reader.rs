@@ -0,0 +1,276 @@
+use super::AudioMeta;
+use super::{AudioReader, AudioReaderError, AudioSource, ResampledReader, resample_audio};
+use audiopus::{Channels, SampleRate};
+use audiopus::coder::Decoder;
+use log::warn;
+use ogg::reading::PacketReader;
+use std::convert::{AsRef, TryFrom};
+use std::fs::File;
+use std::path::Path;
+
+const OPUS_SAMPLE_RATE: u32 = 48_000;
+const OPUS_MAX_FRAME_SIZE: usize = 5760; // 120ms @ 48kHz
+
+pub struct OpusReader {
+    stream: PacketReader<Box<dyn AudioSource>>,
+    decoder: Decoder,
+    channels: u8,
+    pre_skip: usize,
+    eof: bool,
+    samples: Vec<Vec<i16>>,
+}
+
+impl OpusReader {
+    pub fn from_path<T: AsRef<Path>>(opus_path: T) -> Result<OpusReader, AudioReaderError> {
+        // To try open file
+        let opus_file = File::open(opus_path)
+            .map_err(|err| AudioReaderError::CantOpenAudioFile {
+                text: err.to_string(),
+            })?;
+
+        OpusReader::from_reader(opus_file)
+    }
+
+    pub fn from_reader<T: AudioSource + 'static>(reader: T) -> Result<OpusReader, AudioReaderError> {
+        let mut stream = PacketReader::new(Box::new(reader) as Box<dyn AudioSource>);
+
+        // Parse "OpusHead" packet
+        let head_packet = match stream.read_packet() {
+            Ok(Some(pkt)) => pkt,
+            Ok(None) => return Err(AudioReaderError::CantDecodeAudioFile {
+                text: String::from("Missing opus header"),
+            }),
+            Err(err) => return Err(AudioReaderError::CantDecodeAudioFile {
+                text: err.to_string(),
+            }),
+        };
+
+        let (channels, pre_skip) = match head_packet.data.as_slice() {
+            [b'O', b'p', b'u', b's', b'H', b'e', b'a', b'd', _version, channels, skip_0, skip_1, ..] => {
+                (*channels, u16::from_le_bytes([*skip_0, *skip_1]) as usize)
+            },
+            _ => return Err(AudioReaderError::CantDecodeAudioFile {
+                text: String::from("Invalid opus header"),
+            }),
+        };
+
+        let decoder_channels = match channels {
+            1 => Channels::Mono,
+            2 => Channels::Stereo,
+            _ => return Err(AudioReaderError::CantDecodeAudioFile {
+                text: format!("Unsupported opus channel count of {}", channels),
+            }),
+        };
+
+        // Skip "OpusTags" packet
+        if let Err(err) = stream.read_packet() {
+            return Err(AudioReaderError::CantDecodeAudioFile {
+                text: err.to_string(),
+            });
+        }
+
+        let decoder = Decoder::new(SampleRate::Hz48000, decoder_channels)
+            .map_err(|err| AudioReaderError::CantDecodeAudioFile {
+                text: err.to_string(),
+            })?;
+
+        Ok(OpusReader {
+            stream,
+            decoder,
+            channels,
+            pre_skip,
+            eof: false,
+            samples: Vec::new(),
+        })
+    }
+}
+
+impl AudioMeta for OpusReader {
+    fn get_channel_count(&self) -> u8 {
+        self.channels
+    }
+
+    fn get_sample_rate(&self) -> u32 {
+        OPUS_SAMPLE_RATE
+    }
+}
+
+impl AudioReader for OpusReader {
+    fn read_to_end(&mut self) {
+        if self.eof {
+            return
+        }
+
+        let channel_count = self.get_channel_count() as usize;
+
+        // Add vector for each channel
+        for _ in 0..channel_count {
+            self.samples.push(Vec::new());
+        }
+
+        let mut packet_index = 0;
+        let mut decoded = vec![0i16; OPUS_MAX_FRAME_SIZE * channel_count];
+        let mut skip_count = self.pre_skip;
+
+        loop {
+            // Read packet
+            // If error or no packet data, break out of loop
+            let packet = match self.stream.read_packet() {
+                Ok(packet_res) => match packet_res {
+                    Some(pkt) => pkt,
+                    None => break,
+                },
+                Err(err) => {
+                    warn!("Error reading opus packet {}: {}", packet_index, &err);
+                    break;
+                },
+            };
+
+            packet_index += 1;
+
+            if packet.data.is_empty() {
+                continue;
+            }
+
+            // Decode packet (interleaved samples)
+            let input = audiopus::packet::Packet::try_from(&packet.data).ok();
+            let output = match audiopus::MutSignals::try_from(&mut decoded[..]) {
+                Ok(output) => output,
+                Err(_) => break,
+            };
+
+            let frame_count = match self.decoder.decode(input, output, false) {
+                Ok(count) => count,
+                Err(err) => {
+                    // Skip bad packet
+                    warn!("Error decoding opus packet {}: {}", packet_index, &err);
+                    continue;
+                },
+            };
+
+            // Iterate over frames and append samples to each channel
+            for frame in decoded[..(frame_count * channel_count)].chunks(channel_count) {
+                if skip_count > 0 {
+                    // Discard encoder delay
+                    skip_count -= 1;
+                    continue;
+                }
+
+                for (i, sample) in frame.iter().enumerate() {
+                    self.samples[i].push(*sample);
+                }
+            }
+        }
+
+        self.eof = true;
+    }
+
+    fn get_samples<'a>(&'a self) -> &'a Vec<Vec<i16>> {
+        &self.samples
+    }
+
+    fn resample(&self, sample_rate: u32) -> Option<ResampledReader> {
+        resample_audio(self, sample_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::open_audio_bytes;
+    use ogg::writing::{PacketWriteEndInfo, PacketWriter};
+    use std::io::Cursor;
+
+    // 20ms frame of silence (CELT fullband, mono)
+    const SILENT_PACKET: [u8; 3] = [0xF8, 0xFF, 0xFE];
+    const SILENT_PACKET_FRAMES: usize = 960;
+
+    fn create_opus_stream(channels: u8, pre_skip: u16, packet_count: usize) -> Vec<u8> {
+        let mut head = b"OpusHead".to_vec();
+        head.push(1); // Version
+        head.push(channels);
+        head.extend_from_slice(&pre_skip.to_le_bytes());
+        head.extend_from_slice(&OPUS_SAMPLE_RATE.to_le_bytes());
+        head.extend_from_slice(&0u16.to_le_bytes()); // Output gain
+        head.push(0); // Channel mapping family
+
+        let mut tags = b"OpusTags".to_vec();
+        tags.extend_from_slice(&0u32.to_le_bytes()); // Vendor string length
+        tags.extend_from_slice(&0u32.to_le_bytes()); // Comment count
+
+        let mut writer = PacketWriter::new(Vec::new());
+        writer.write_packet(head.into_boxed_slice(), 1, PacketWriteEndInfo::EndPage, 0).unwrap();
+        writer.write_packet(tags.into_boxed_slice(), 1, PacketWriteEndInfo::EndPage, 0).unwrap();
+
+        for i in 0..packet_count {
+            let end_info = match i + 1 {
+                n if n == packet_count => PacketWriteEndInfo::EndStream,
+                _ => PacketWriteEndInfo::NormalPacket,
+            };
+
+            let granule = ((i + 1) * SILENT_PACKET_FRAMES) as u64;
+            writer.write_packet(Box::new(SILENT_PACKET), 1, end_info, granule).unwrap();
+        }
+
+        writer.into_inner()
+    }
+
+    #[test]
+    fn decode_trims_pre_skip() {
+        let data = create_opus_stream(2, 312, 3);
+
+        let mut reader = OpusReader::from_reader(Cursor::new(data)).unwrap();
+        reader.read_to_end();
+
+        let samples = reader.get_samples();
+        assert_eq!(2, reader.get_channel_count());
+        assert_eq!(OPUS_SAMPLE_RATE, reader.get_sample_rate());
+        assert_eq!(2, samples.len());
+        assert!(samples.iter().all(|s| s.len() == (3 * SILENT_PACKET_FRAMES) - 312));
+    }
+
+    #[test]
+    fn decode_pre_skip_longer_than_first_packet() {
+        let data = create_opus_stream(1, 1000, 2);
+
+        let mut reader = OpusReader::from_reader(Cursor::new(data)).unwrap();
+        reader.read_to_end();
+
+        let samples = reader.get_samples();
+        assert_eq!(1, samples.len());
+        assert_eq!((2 * SILENT_PACKET_FRAMES) - 1000, samples[0].len());
+    }
+
+    #[test]
+    fn reject_more_than_two_channels() {
+        let data = create_opus_stream(6, 312, 1);
+
+        match OpusReader::from_reader(Cursor::new(data)) {
+            Err(AudioReaderError::CantDecodeAudioFile { text }) => assert!(text.contains("channel count of 6")),
+            _ => panic!("Expected unsupported channel count error"),
+        }
+    }
+
+    #[test]
+    fn open_opus_encoded_ogg() {
+        let data = create_opus_stream(2, 312, 2);
+
+        // Not vorbis so should fall back to opus reader
+        let mut reader = open_audio_bytes(data, "song.ogg").unwrap();
+        reader.read_to_end();
+
+        assert_eq!(2, reader.get_channel_count());
+        assert_eq!(OPUS_SAMPLE_RATE, reader.get_sample_rate());
+        assert_eq!((2 * SILENT_PACKET_FRAMES) - 312, reader.get_samples()[0].len());
+    }
+
+    #[test]
+    fn open_invalid_ogg_keeps_vorbis_error() {
+        let data = b"OggS not really an ogg file".to_vec();
+
+        match open_audio_bytes(data, "song.ogg") {
+            Err(AudioReaderError::CantDecodeAudioFile { .. }) => {},
+            _ => panic!("Expected decode error"),
+        }
+    }
+}
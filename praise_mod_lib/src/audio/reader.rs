@@ -1,5 +1,6 @@
 use super::AudioMeta;
-use super::AudioReaderError;
+use super::{AudioReaderError, OpusReader, SymphoniaReader};
+use crate::vfs::FileSource;
 use fon::chan::Ch16;
 use fon::{Audio, Sink, Stream};
 use lewton::VorbisError;
@@ -7,7 +8,8 @@ use lewton::audio::AudioReadError;
 use lewton::inside_ogg::OggStreamReader;
 use log::{debug, warn};
 use std::{convert::AsRef, iter::Zip};
-use std::fs::File;
+use std::fs::{File, read};
+use std::io::{Cursor, Read, Seek};
 use std::path::{Path};
 
 pub trait AudioReader {
@@ -16,8 +18,113 @@ pub trait AudioReader {
     fn resample(&self, sample_rate: u32) -> Option<ResampledReader>;
 }
 
+pub const SUPPORTED_AUDIO_EXTENSIONS: [&str; 6] = ["ogg", "opus", "mp3", "wav", "flac", "mogg"];
+
+const MOGG_UNENCRYPTED_VERSION: u32 = 0x0A;
+
+// Any audio reader that can be decoded on another thread
+pub trait AudioFileReader: AudioReader + AudioMeta + Send {}
+
+impl<T: AudioReader + AudioMeta + Send> AudioFileReader for T {}
+
+pub fn is_supported_audio_file<T: AsRef<Path>>(audio_path: T) -> bool {
+    match get_lowercase_extension(audio_path.as_ref()) {
+        Some(ext) => SUPPORTED_AUDIO_EXTENSIONS.contains(&ext.as_str()),
+        None => false,
+    }
+}
+
+pub fn open_audio_file<T: AsRef<Path>>(audio_path: T) -> Result<Box<dyn AudioFileReader>, AudioReaderError> {
+    let audio_path = audio_path.as_ref();
+
+    // Audio is fully decoded into memory anyways
+    let data = read(audio_path)
+        .map_err(|err| AudioReaderError::CantOpenAudioFile {
+            text: err.to_string(),
+        })?;
+
+    open_audio_bytes(data, audio_path)
+}
+
+pub fn open_audio_source(source: &dyn FileSource, file_name: &str) -> Result<Box<dyn AudioFileReader>, AudioReaderError> {
+    let data = source
+        .read_file(file_name)
+        .map_err(|err| AudioReaderError::CantOpenAudioFile {
+            text: err.to_string(),
+        })?;
+
+    open_audio_bytes(data, file_name)
+}
+
+pub fn open_audio_bytes<T: AsRef<Path>>(data: Vec<u8>, file_name: T) -> Result<Box<dyn AudioFileReader>, AudioReaderError> {
+    let file_name = file_name.as_ref();
+    let ext = get_lowercase_extension(file_name).unwrap_or_default();
+
+    // Select reader from extension
+    match ext.as_str() {
+        "ogg" => match OggReader::from_reader(Cursor::new(data.to_owned())) {
+            Ok(reader) => Ok(Box::new(reader)),
+            Err(AudioReaderError::CantDecodeAudioFile { text }) => {
+                // Some .ogg files are opus encoded
+                debug!("Can't decode \"{}\" as vorbis ({}), trying opus", file_name.display(), &text);
+
+                OpusReader::from_reader(Cursor::new(data))
+                    .map(|reader| Box::new(reader) as Box<dyn AudioFileReader>)
+                    .map_err(|_| AudioReaderError::CantDecodeAudioFile { text })
+            },
+            Err(err) => Err(err),
+        },
+        "opus" => Ok(Box::new(OpusReader::from_reader(Cursor::new(data))?)),
+        "mogg" => Ok(Box::new(OggReader::from_reader(Cursor::new(strip_mogg_header(data)?))?)),
+        "mp3" | "wav" | "flac" => Ok(Box::new(SymphoniaReader::from_reader(Cursor::new(data), Some(&ext))?)),
+        _ => Err(AudioReaderError::UnsupportedAudioFormat {
+            ext,
+        }),
+    }
+}
+
+fn strip_mogg_header(mut data: Vec<u8>) -> Result<Vec<u8>, AudioReaderError> {
+    // Rock Band multitrack ogg: <version: u32> <ogg offset: u32> <seek table> <ogg data>
+    let (version, ogg_offset) = match data.get(..8) {
+        Some(header) => (
+            u32::from_le_bytes([header[0], header[1], header[2], header[3]]),
+            u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize,
+        ),
+        None => return Err(AudioReaderError::CantDecodeAudioFile {
+            text: String::from("Invalid mogg header"),
+        }),
+    };
+
+    if version != MOGG_UNENCRYPTED_VERSION {
+        return Err(AudioReaderError::EncryptedMogg {
+            version,
+        });
+    }
+
+    if ogg_offset > data.len() {
+        return Err(AudioReaderError::CantDecodeAudioFile {
+            text: String::from("Invalid mogg header"),
+        });
+    }
+
+    data.drain(..ogg_offset);
+    Ok(data)
+}
+
+fn get_lowercase_extension(audio_path: &Path) -> Option<String> {
+    audio_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+}
+
+// Seekable audio data (file or in-memory)
+pub trait AudioSource: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> AudioSource for T {}
+
 pub struct OggReader {
-    stream: OggStreamReader<File>,
+    stream: OggStreamReader<Box<dyn AudioSource>>,
     eof: bool,
     samples: Vec<Vec<i16>>,
 }
@@ -61,8 +168,23 @@ impl OggReader {
             })
         };
 
+        OggReader::from_reader(ogg_file)
+    }
+
+    pub fn from_source(source: &dyn FileSource, file_name: &str) -> Result<OggReader, AudioReaderError> {
+        let data = match source.read_file(file_name) {
+            Ok(data) => data,
+            Err(err) => return Err(AudioReaderError::CantOpenAudioFile {
+                text: err.to_string(),
+            })
+        };
+
+        OggReader::from_reader(Cursor::new(data))
+    }
+
+    pub fn from_reader<T: AudioSource + 'static>(reader: T) -> Result<OggReader, AudioReaderError> {
         // Try to initially parse stream
-        let stream = match OggStreamReader::new(ogg_file) {
+        let stream = match OggStreamReader::new(Box::new(reader) as Box<dyn AudioSource>) {
             Ok(reader) => reader,
             Err(err) => return Err(AudioReaderError::CantDecodeAudioFile {
                 text: err.to_string(),
@@ -146,10 +268,11 @@ impl AudioReader for OggReader {
     }
 }
 
-fn resample_audio<T: AudioReader + AudioMeta>(audio: &T, sample_rate: u32) -> Option<ResampledReader> {
+pub(crate) fn resample_audio<T: AudioReader + AudioMeta + ?Sized>(audio: &T, sample_rate: u32) -> Option<ResampledReader> {
     let in_sample_rate = audio.get_sample_rate();
 
     let samples = match audio.get_channel_count() {
+        0 => return None,
         1 => {
             vec![
                 resample_mono(&audio.get_samples()[0], in_sample_rate, sample_rate)
@@ -177,7 +300,10 @@ fn resample_audio<T: AudioReader + AudioMeta>(audio: &T, sample_rate: u32) -> Op
                 resample_mono(&audio.get_samples()[1], in_sample_rate, sample_rate)
             ]
         },
-        _ => return None
+        _ => audio.get_samples()
+            .iter()
+            .map(|channel| resample_mono(channel, in_sample_rate, sample_rate))
+            .collect(),
     };
 
     Some(ResampledReader {
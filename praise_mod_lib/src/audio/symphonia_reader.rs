@@ -0,0 +1,244 @@
+use super::AudioMeta;
+use super::{AudioReader, AudioReaderError, ResampledReader, resample_audio};
+use log::warn;
+use std::convert::AsRef;
+use std::fs::File;
+use std::io::ErrorKind;
+use std::path::Path;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatOptions, FormatReader};
+use symphonia::core::io::{MediaSource, MediaSourceStream};
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+// Decodes mp3, wav and flac audio
+pub struct SymphoniaReader {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+    channels: u8,
+    sample_rate: u32,
+    eof: bool,
+    samples: Vec<Vec<i16>>,
+}
+
+impl SymphoniaReader {
+    pub fn from_path<T: AsRef<Path>>(audio_path: T) -> Result<SymphoniaReader, AudioReaderError> {
+        let audio_path = audio_path.as_ref();
+
+        // To try open file
+        let audio_file = File::open(audio_path)
+            .map_err(|err| AudioReaderError::CantOpenAudioFile {
+                text: err.to_string(),
+            })?;
+
+        let ext = audio_path
+            .extension()
+            .and_then(|e| e.to_str());
+
+        SymphoniaReader::from_reader(audio_file, ext)
+    }
+
+    pub fn from_reader<T: MediaSource + 'static>(reader: T, ext: Option<&str>) -> Result<SymphoniaReader, AudioReaderError> {
+        // Use extension as hint if found
+        let mut hint = Hint::new();
+        if let Some(ext) = ext {
+            hint.with_extension(ext);
+        }
+
+        let stream = MediaSourceStream::new(Box::new(reader), Default::default());
+
+        // Try to initially parse stream
+        let probed = symphonia::default::get_probe()
+            .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+            .map_err(|err| AudioReaderError::CantDecodeAudioFile {
+                text: err.to_string(),
+            })?;
+
+        let format = probed.format;
+
+        // Find first decodable audio track
+        let track = format
+            .tracks()
+            .iter()
+            .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+            .ok_or(AudioReaderError::CantDecodeAudioFile {
+                text: String::from("No audio track found"),
+            })?;
+
+        let track_id = track.id;
+        let sample_rate = track.codec_params.sample_rate.unwrap_or(44_100);
+        let channels = track.codec_params.channels
+            .map(|c| c.count() as u8)
+            .unwrap_or(2);
+
+        let decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())
+            .map_err(|err| AudioReaderError::CantDecodeAudioFile {
+                text: err.to_string(),
+            })?;
+
+        Ok(SymphoniaReader {
+            format,
+            decoder,
+            track_id,
+            channels,
+            sample_rate,
+            eof: false,
+            samples: Vec::new(),
+        })
+    }
+}
+
+impl AudioMeta for SymphoniaReader {
+    fn get_channel_count(&self) -> u8 {
+        self.channels
+    }
+
+    fn get_sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+}
+
+impl AudioReader for SymphoniaReader {
+    fn read_to_end(&mut self) {
+        if self.eof {
+            return
+        }
+
+        // Add vector for each channel
+        for _ in 0..self.get_channel_count() {
+            self.samples.push(Vec::new());
+        }
+
+        let mut packet_index = 0;
+
+        loop {
+            // Read packet
+            // If error or end of stream, break out of loop
+            let packet = match self.format.next_packet() {
+                Ok(pkt) => pkt,
+                Err(SymphoniaError::IoError(err)) if err.kind() == ErrorKind::UnexpectedEof => break,
+                Err(err) => {
+                    warn!("Error reading audio packet {}: {}", packet_index, &err);
+                    break;
+                },
+            };
+
+            packet_index += 1;
+
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+
+            // Decode packet
+            // Skip packet if bad audio or break out of loop otherwise
+            let decoded = match self.decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                Err(SymphoniaError::DecodeError(err)) => {
+                    warn!("Error decoding audio packet {}: {}", packet_index, err);
+                    continue;
+                },
+                Err(err) => {
+                    warn!("Error decoding audio packet {}: {}", packet_index, &err);
+                    break;
+                },
+            };
+
+            let spec = *decoded.spec();
+            let frame_count = decoded.frames();
+
+            let mut sample_buffer = SampleBuffer::<i16>::new(decoded.capacity() as u64, spec);
+            sample_buffer.copy_planar_ref(decoded);
+
+            // Iterate over channels and append samples
+            for (i, channel_samples) in sample_buffer
+                .samples()
+                .chunks(frame_count.max(1))
+                .take(self.samples.len())
+                .enumerate() {
+                self.samples[i].extend_from_slice(channel_samples);
+            }
+        }
+
+        self.eof = true;
+    }
+
+    fn get_samples<'a>(&'a self) -> &'a Vec<Vec<i16>> {
+        &self.samples
+    }
+
+    fn resample(&self, sample_rate: u32) -> Option<ResampledReader> {
+        resample_audio(self, sample_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn create_wav(channels: &[Vec<i16>], sample_rate: u32) -> Vec<u8> {
+        let channel_count = channels.len() as u16;
+        let frame_count = channels[0].len();
+        let block_align = channel_count * 2;
+        let data_size = (frame_count * block_align as usize) as u32;
+
+        let mut data = b"RIFF".to_vec();
+        data.extend_from_slice(&(36 + data_size).to_le_bytes());
+        data.extend_from_slice(b"WAVE");
+
+        data.extend_from_slice(b"fmt ");
+        data.extend_from_slice(&16u32.to_le_bytes());
+        data.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        data.extend_from_slice(&channel_count.to_le_bytes());
+        data.extend_from_slice(&sample_rate.to_le_bytes());
+        data.extend_from_slice(&(sample_rate * block_align as u32).to_le_bytes());
+        data.extend_from_slice(&block_align.to_le_bytes());
+        data.extend_from_slice(&16u16.to_le_bytes()); // Bits per sample
+
+        data.extend_from_slice(b"data");
+        data.extend_from_slice(&data_size.to_le_bytes());
+
+        // Interleave samples
+        for i in 0..frame_count {
+            for channel in channels.iter() {
+                data.extend_from_slice(&channel[i].to_le_bytes());
+            }
+        }
+
+        data
+    }
+
+    fn decode_wav(data: Vec<u8>) -> SymphoniaReader {
+        let mut reader = SymphoniaReader::from_reader(Cursor::new(data), Some("wav")).unwrap();
+        reader.read_to_end();
+        reader
+    }
+
+    #[test]
+    fn decode_mono_wav() {
+        let channels = vec![(0..2000).map(|i| (i * 16) as i16).collect::<Vec<_>>()];
+
+        let reader = decode_wav(create_wav(&channels, 44_100));
+
+        assert_eq!(1, reader.get_channel_count());
+        assert_eq!(44_100, reader.get_sample_rate());
+        assert_eq!(&channels, reader.get_samples());
+    }
+
+    #[test]
+    fn decode_wav_with_more_than_two_channels() {
+        let channels = (0..6i16)
+            .map(|c| (0..5000i16).map(|i| (i % 100) * 100 + c).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        let reader = decode_wav(create_wav(&channels, 48_000));
+
+        assert_eq!(6, reader.get_channel_count());
+        assert_eq!(48_000, reader.get_sample_rate());
+        assert_eq!(&channels, reader.get_samples());
+    }
+}
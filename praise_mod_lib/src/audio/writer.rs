@@ -1,3 +1,4 @@
+use crate::audio::AudioWriterError;
 use std::convert::AsRef;
 use std::io::Write;
 use std::fs::File;
@@ -22,8 +23,14 @@ impl AudioWriter {
         }
     }
 
-    pub fn save_as_ogg<T: AsRef<Path>>(&self, ogg_path: T, quality: Option<f32>) {
-        // TODO: Handle exceptions
+    pub fn save_as_ogg<T: AsRef<Path>>(&self, ogg_path: T, quality: Option<f32>) -> Result<(), AudioWriterError> {
+        let encode_error = |err| AudioWriterError::CantEncodeAudio {
+            text: format!("{:?}", err),
+        };
+
+        let write_error = |err: std::io::Error| AudioWriterError::CantWriteAudioFile {
+            text: err.to_string(),
+        };
 
         // Create encoder
         let mut encoder = Encoder::new(
@@ -32,61 +39,58 @@ impl AudioWriter {
             match quality {
                 Some(q) => q,
                 None => 0.5,
-        }).unwrap();
+        }).map_err(encode_error)?;
 
         // Create file
-        let mut ogg_file = File::create(ogg_path).unwrap();
+        let mut ogg_file = File::create(ogg_path).map_err(write_error)?;
 
         // Encode data + write to file
-        let data = encoder.encode(&self.samples_as_i16()).unwrap();
-        ogg_file.write(&data).unwrap();
+        let data = encoder.encode(&self.samples_as_i16()).map_err(encode_error)?;
+        ogg_file.write_all(&data).map_err(write_error)?;
 
         // Finalize file
-        let data = encoder.flush().unwrap();
-        ogg_file.write(&data).unwrap();
+        let data = encoder.flush().map_err(encode_error)?;
+        ogg_file.write_all(&data).map_err(write_error)?;
+
+        Ok(())
     }
 
     pub fn merge_from(&mut self, source: &Vec<Vec<i16>>) {
-        let source_channels = source.len() as u32;
+        let source_channels = source.len();
 
-        let is_mono = match source_channels {
-            n if n == 0 || n > 2 => return,
-            1 => true,
-            _ => false,
-        };
+        if source_channels == 0 {
+            return
+        }
 
-        let left_src = &source[0];
-        let right_src = match is_mono {
-            true => &source[0],
-            _ => &source[1],
+        // Multitrack audio (i.e. .mogg) is mixed down to mono since channel panning isn't known
+        let get_sample = |i: usize, channel: usize| -> i32 {
+            match source_channels {
+                1 => source[0][i] as i32,
+                2 => source[channel][i] as i32,
+                _ => source
+                    .iter()
+                    .map(|s| s.get(i).copied().unwrap_or_default() as i32)
+                    .sum(),
+            }
         };
 
-        let sample_length = self.samples.len() / self.channels as usize;
+        let channels = self.channels as usize;
+        let sample_length = self.samples.len() / channels;
         let source_sample_length = source[0].len();
 
         // Merge audio
-        let source_sample_length_itr = source_sample_length * source_channels as usize;
-        for (i, sample) in self.samples
-            .iter_mut()
-            .enumerate() {
-                if i == source_sample_length_itr {
-                    break
-                }
-
-                let sample_idx = i >> 1;
-                let to_mix_sample = match i % 2 {
-                    0 => left_src[sample_idx],
-                    _ => right_src[sample_idx],
-                };
-
-                // *sample = mix_samples(*sample, to_mix_sample);
-                *sample = mix_samples_i32(*sample, to_mix_sample as i32);
+        for i in 0..sample_length.min(source_sample_length) {
+            for channel in 0..channels {
+                let sample = &mut self.samples[(i * channels) + channel];
+                *sample = mix_samples_i32(*sample, get_sample(i, channel));
+            }
         }
 
         // Append audio
         for i in sample_length..source_sample_length {
-            self.samples.push(left_src[i] as i32);
-            self.samples.push(right_src[i] as i32);
+            for channel in 0..channels {
+                self.samples.push(get_sample(i, channel));
+            }
         }
     }
 
@@ -111,6 +115,14 @@ impl AudioWriter {
         }
     }
 
+    pub fn create_silent_copy(&self) -> AudioWriter {
+        AudioWriter {
+            channels: self.channels,
+            sample_rate: self.sample_rate,
+            samples: vec![0; self.samples.len()],
+        }
+    }
+
     pub fn get_length_in_ms(&self) -> f64 {
         if self.samples.is_empty() {
             return 0.0
@@ -127,23 +139,27 @@ impl AudioWriter {
     }
 
     pub fn fix_clipping(&mut self) {
-        if self.samples.is_empty() {
-            return
-        }
+        let scale = self.get_clipping_scale();
+        self.scale_samples(scale);
+    }
 
+    pub fn get_clipping_scale(&self) -> f64 {
         // Find max value
         let abs_max = self.samples
             .iter()
             .map(|s| s.abs())
             .max()
-            .unwrap();
+            .unwrap_or_default();
 
         if abs_max < MAX_SAMPLE_VALUE {
-            return
+            return 1.0
         }
 
-        let scale = (abs_max as f64) / (MAX_SAMPLE_VALUE as f64);
+        (abs_max as f64) / (MAX_SAMPLE_VALUE as f64)
+    }
 
+    pub fn scale_samples(&mut self, scale: f64) {
+        // Values are still clamped in case scale was calculated from other audio
         for s in self.samples.iter_mut() {
             let new_value = ((*s as f64) / scale) as i32;
 
@@ -157,6 +173,13 @@ impl AudioWriter {
         }
     }
 
+    pub fn pad_start(&mut self, length_ms: f64) {
+        let pad_samples = ((length_ms / 1000.0) * self.sample_rate as f64) as usize * (self.channels as usize);
+
+        // Insert silence before existing audio
+        self.samples.splice(0..0, std::iter::repeat(0).take(pad_samples));
+    }
+
     pub fn make_silent(&mut self) {
         for s in self.samples.iter_mut() {
             *s = 0;
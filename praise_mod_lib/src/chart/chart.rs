@@ -1,28 +1,29 @@
+use crate::chart::ChartParseError;
 use crate::chart::parser::*;
 use crate::shared::*;
-use std::error::Error;
-use std::fs::read_to_string;
-use std::path::{Path, PathBuf};
+use crate::vfs::FileSource;
+use std::fs::read;
+use std::path::Path;
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum SyncEventType {
     Beat(u32), // bpm * 1000
     TimeSignature(u32, Option<u32>), // 2nd number is encoded as base 2 power (3/8 = 3,3)
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct SyncEvent {
     pub pos: u64,
     pub pos_realtime: f64, // Milliseconds
     pub value: SyncEventType,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct SyncTrack {
     pub events: Vec<SyncEvent>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum GuitarEventType {
     Note(u32),
     Starpower,
@@ -31,7 +32,7 @@ pub enum GuitarEventType {
     Open,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct GuitarEvent {
     pub pos: u64,
     pub pos_realtime: f64, // Milliseconds
@@ -40,60 +41,119 @@ pub struct GuitarEvent {
     pub value: GuitarEventType,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct GuitarTrack {
     pub name: String,
     pub events: Vec<GuitarEvent>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
+pub enum TextEventType {
+    PhraseStart,
+    PhraseEnd,
+    Lyric(String),
+    Section(String),
+    Other(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct TextEvent {
+    pub pos: u64,
+    pub pos_realtime: f64, // Milliseconds
+    pub value: TextEventType,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct EventTrack {
+    pub events: Vec<TextEvent>,
+}
+
+#[derive(Debug, PartialEq)]
 pub struct SongChart {
     pub resolution: u16,
+    pub offset: f64, // Milliseconds
+    pub song_values: Vec<(String, String)>, // Other [Song] values as written (i.e. quoted strings)
     pub sync_track: SyncTrack,
+    pub event_track: EventTrack,
     pub guitar_tracks: Vec<GuitarTrack>,
 }
 
 impl SongChart {
-    pub fn from_path(path: &Path) -> Result<SongChart, Box<dyn Error>> {
-        let text = read_to_string(path)?;
-        let mut chart = parse_chart(&text)?;
+    pub fn from_path(path: &Path) -> Result<SongChart, ChartParseError> {
+        let data = read(path)
+            .map_err(|err| ChartParseError::CantOpenChartFile {
+                text: err.to_string(),
+            })?;
+
+        SongChart::from_bytes(&data)
+    }
+
+    pub fn from_source(source: &dyn FileSource, file_name: &str) -> Result<SongChart, ChartParseError> {
+        let data = source
+            .read_file(file_name)
+            .map_err(|err| ChartParseError::CantOpenChartFile {
+                text: err.to_string(),
+            })?;
+
+        SongChart::from_bytes(&data)
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<SongChart, ChartParseError> {
+        let text = std::str::from_utf8(data)
+            .map_err(|_| ChartParseError::InvalidTextEncoding)?;
+
+        SongChart::from_text(text)
+    }
+
+    fn from_text(text: &str) -> Result<SongChart, ChartParseError> {
+        // Ignore byte order mark if present
+        let text = text.trim_start_matches('\u{feff}');
+
+        let mut chart = parse_chart(text)?;
         chart.update_realtime_positions();
 
         Ok(chart)
     }
 
-    fn update_realtime_positions(&mut self) {
-        // Add default tempo event if not found
-        if self.sync_track
+    pub(crate) fn get_tempo_events(&self) -> Vec<SyncEvent> {
+        // Time signatures don't affect timing
+        let mut tempo_events = self.sync_track
             .events
             .iter()
-            .any(
-                |e| match e.value {
-                    SyncEventType::Beat(_) => true,
-                    _ => false
-                }) {
-                    self.sync_track.events.insert(0, SyncEvent {
-                        pos: 0,
-                        pos_realtime: 0.0,
-                        value: SyncEventType::Beat(120_000),
-                    });
+            .filter(|e| matches!(e.value, SyncEventType::Beat(_)))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        // Events may be out of order in chart
+        tempo_events.sort_by_key(|e| e.pos);
+
+        // Add default tempo event if not found at start
+        if tempo_events.first().map(|e| e.pos != 0).unwrap_or(true) {
+            tempo_events.insert(0, SyncEvent {
+                pos: 0,
+                pos_realtime: 0.0,
+                value: SyncEventType::Beat(120_000),
+            });
         }
 
-        // Remove ts change events
-        // TODO: Refactor for other music games
-        self.sync_track
-            .events
-            .retain(|e| match e.value {
-                SyncEventType::Beat(_) => true,
-                _ => false
-            });
+        update_realtime_positions_tempo(&mut tempo_events, self.resolution);
+        tempo_events
+    }
+
+    fn update_realtime_positions(&mut self) {
+        // Sync events are kept as-is and only used for calculating positions
+        self.sync_track.events.sort_by_key(|e| e.pos);
+        let tempo_events = self.get_tempo_events();
+
+        // Update positions of tempo + time signature events
+        update_realtime_positions(&mut self.sync_track.events, &tempo_events, self.resolution);
 
-        // Update tempo map positions
-        update_realtime_positions_tempo(&mut self.sync_track.events, self.resolution);
+        // Update positions of global events (lyrics, phrases, etc.)
+        update_realtime_positions(&mut self.event_track.events, &tempo_events, self.resolution);
 
         // Update positions in each guitar/bass track
         for guitar_track in self.guitar_tracks.iter_mut() {
-            update_realtime_positions(&mut guitar_track.events, &self.sync_track.events, self.resolution);
+            update_realtime_positions(&mut guitar_track.events, &tempo_events, self.resolution);
         }
     }
 }
@@ -124,6 +184,32 @@ impl RealtimeNote for GuitarEvent {
     }
 }
 
+impl RealtimeNote for TextEvent {
+    fn get_pos(&self) -> u64 {
+        self.pos
+    }
+
+    fn get_pos_realtime(&self) -> f64 {
+        self.pos_realtime
+    }
+
+    fn get_length(&self) -> u64 {
+        0
+    }
+
+    fn get_length_realtime(&self) -> f64 {
+        0.0
+    }
+
+    fn set_pos_realtime(&mut self, pos: f64) {
+        self.pos_realtime = pos;
+    }
+
+    fn set_length_realtime(&mut self, _length: f64) {
+        // Do nothing
+    }
+}
+
 impl RealtimeNote for SyncEvent {
     fn get_pos(&self) -> u64 {
         self.pos
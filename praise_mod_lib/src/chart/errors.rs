@@ -1,16 +1,41 @@
 use thiserror::Error as ThisError;
-use std::error::Error;
 
 #[derive(Debug, ThisError)]
 pub enum ChartParseError {
+    #[error("Can't open .chart file because of \"{text}\"")]
+    CantOpenChartFile {
+        text: String,
+    },
+    #[error("Chart text is not valid UTF-8")]
+    InvalidTextEncoding,
     #[error("Failed to initially parse .chart")]
     InitialParseFail,
     #[error("Failed to parse \"[Song]\" section")]
     CantParseSongSection,
+    #[error("Invalid resolution of 0 ticks per beat")]
+    InvalidResolution,
     #[error("Failed to parse \"[SyncTrack]\" section")]
     CantParseSyncTrackSection,
+    #[error("Invalid tempo of 0 bpm at position {pos}")]
+    InvalidTempo {
+        pos: u64,
+    },
+    #[error("Failed to parse \"[Events]\" section")]
+    CantParseEventsSection,
     #[error("Failed to parse guitar/bass \"[{track_name}]\" section")]
     CantParseGuitarBassTrackSection {
         track_name: String,
     },
+}
+
+#[derive(Debug, ThisError)]
+pub enum ChartWriteError {
+    #[error("Can't write text event \"{text}\" containing quotes or line breaks")]
+    UnsupportedText {
+        text: String,
+    },
+    #[error("Can't write song value \"{key}\" with invalid name or line breaks")]
+    UnsupportedSongValue {
+        key: String,
+    },
 }
\ No newline at end of file
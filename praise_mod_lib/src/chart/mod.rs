@@ -1,6 +1,7 @@
 mod chart;
 mod errors;
 mod parser;
+mod writer;
 
 pub use self::chart::*;
 pub use self::errors::*;
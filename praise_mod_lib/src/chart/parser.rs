@@ -97,28 +97,21 @@ fn get_key_value_pairs(text: &str) -> IResult<&str, Vec<(&str, &str)>> {
     many0(get_key_value_pair)(text)
 }
 
-fn get_key_value_pairs_mapped(text: &str) -> IResult<&str, HashMap<&str, &str>> {
-    let mut mapper = map(
-        get_key_value_pairs,
-        |value_pair| value_pair
-            .into_iter()
-            .collect::<HashMap<&str, &str>>(),
-    );
-
-    mapper(text)
-}
-
-fn get_sync_track_parsed(text: &str) -> Result<Vec<(u64, &str, u32)>, ChartParseError> {
+fn get_sync_track_parsed(text: &str) -> Result<Vec<(u64, &str, u32, Option<u32>)>, ChartParseError> {
     let (_, events) = get_key_value_pairs(text)
         .map_err(|_| ChartParseError::CantParseSyncTrackSection)?;
     
-    let res: Vec<(u64, &str, u32)> = events
+    let res: Vec<(u64, &str, u32, Option<u32>)> = events
         .into_iter()
         .map(|(pos, raw_text)| {
+            let pos = pos
+                .parse::<u64>()
+                .map_err(|_| ChartParseError::CantParseSyncTrackSection)?;
+
             let split_text: Vec<&str> = raw_text.split_whitespace().collect();
 
-            // (pos, ev_type, value)
-            (pos.parse::<u64>().unwrap(),
+            // (pos, ev_type, value, value_2)
+            Ok((pos,
                 match split_text.get(0) {
                     Some(v) => *v,
                     None => &"",
@@ -126,13 +119,61 @@ fn get_sync_track_parsed(text: &str) -> Result<Vec<(u64, &str, u32)>, ChartParse
                 match split_text.get(1) {
                     Some(v) => v.parse().unwrap_or_default(),
                     None => 0,
-                })
+                },
+                split_text
+                    .get(2)
+                    .and_then(|v| v.parse().ok())))
         })
-        .collect();
+        .collect::<Result<_, ChartParseError>>()?;
     
     Ok(res)
 }
 
+fn get_events_track_parsed(text: &str) -> Result<Vec<(u64, &str, &str)>, ChartParseError> {
+    let (_, events) = get_key_value_pairs(text)
+        .map_err(|_| ChartParseError::CantParseEventsSection)?;
+
+    let res: Vec<(u64, &str, &str)> = events
+        .into_iter()
+        .map(|(pos, raw_text)| {
+            let pos = pos
+                .parse::<u64>()
+                .map_err(|_| ChartParseError::CantParseEventsSection)?;
+
+            let (ev_type, ev_text) = match raw_text.split_once(char::is_whitespace) {
+                Some((ev_type, ev_text)) => (ev_type, ev_text.trim()),
+                None => (raw_text, ""),
+            };
+
+            // Text is usually quoted but some older charts leave it bare
+            let ev_text = ev_text
+                .strip_prefix('"')
+                .map(|t| t.strip_suffix('"').unwrap_or(t))
+                .unwrap_or(ev_text);
+
+            // (pos, ev_type, text)
+            Ok((pos, ev_type, ev_text))
+        })
+        .collect::<Result<_, ChartParseError>>()?;
+
+    Ok(res)
+}
+
+fn map_text_event(text: &str) -> TextEventType {
+    let (name, value) = match text.split_once(' ') {
+        Some((name, value)) => (name, value),
+        None => (text, ""),
+    };
+
+    match name {
+        "phrase_start" => TextEventType::PhraseStart,
+        "phrase_end" => TextEventType::PhraseEnd,
+        "lyric" => TextEventType::Lyric(value.trim().to_string()),
+        "section" => TextEventType::Section(value.trim().to_string()),
+        _ => TextEventType::Other(text.to_string()),
+    }
+}
+
 fn get_guitar_track_parsed<'a>(text: &'a str, track_name: &'a str) -> Result<Vec<(u64, &'a str, u32, u32)>, ChartParseError> {
     let (_, events) = get_key_value_pairs(text)
         .map_err(|_| ChartParseError::CantParseGuitarBassTrackSection{
@@ -142,10 +183,16 @@ fn get_guitar_track_parsed<'a>(text: &'a str, track_name: &'a str) -> Result<Vec
     let res: Vec<(u64, &str, u32, u32)> = events
         .into_iter()
         .map(|(pos, raw_text)| {
+            let pos = pos
+                .parse::<u64>()
+                .map_err(|_| ChartParseError::CantParseGuitarBassTrackSection {
+                    track_name: track_name.to_string(),
+                })?;
+
             let split_text: Vec<&str> = raw_text.split_whitespace().collect();
 
             // (pos, ev_type, value_1, value_2)
-            (pos.parse::<u64>().unwrap(),
+            Ok((pos,
                 match split_text.get(0) {
                     Some(v) => *v,
                     None => &"",
@@ -157,9 +204,9 @@ fn get_guitar_track_parsed<'a>(text: &'a str, track_name: &'a str) -> Result<Vec
                 match split_text.get(2) {
                     Some(v) => v.parse().unwrap_or_default(),
                     None => 0,
-                })
+                }))
         })
-        .collect();
+        .collect::<Result<_, ChartParseError>>()?;
 
     Ok(res)
 }
@@ -169,22 +216,48 @@ pub fn parse_chart(text: &str) -> Result<SongChart, ChartParseError> {
         .map_err(|_| ChartParseError::InitialParseFail)?;
 
     let mut resolution = 480u16;
+    let mut offset = 0.0;
+    let mut song_values = Vec::new();
     let mut sync_track = SyncTrack {
         events: Vec::new(),
     };
 
+    let mut event_track = EventTrack {
+        events: Vec::new(),
+    };
+
     let mut guitar_tracks = Vec::new();
 
     // Parse song/chart metadata
     if let Some(song_section) = mapped_sections.get("Song") {
-        let (_, song_meta)= get_key_value_pairs_mapped(song_section)
+        let (_, song_pairs) = get_key_value_pairs(song_section)
             .map_err(|_| ChartParseError::CantParseSongSection)?;
 
-        // For now only care about resolution
+        // Other values are kept so they can be written back
+        song_values = song_pairs
+            .iter()
+            .filter(|(key, _)| !matches!(*key, "Resolution" | "Offset"))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+
+        let song_meta = song_pairs
+            .into_iter()
+            .collect::<HashMap<&str, &str>>();
+
+        // Only resolution + offset are used for parsing
         if let Some(res_text) = song_meta.get("Resolution") {
             // Update tpq if found in song meta
-            if let Ok(res) = res_text.parse::<u16>() {
-                resolution = res;
+            match res_text.parse::<u16>() {
+                Ok(0) => return Err(ChartParseError::InvalidResolution),
+                Ok(res) => resolution = res,
+                _ => {},
+            }
+        }
+
+        if let Some(offset_text) = song_meta.get("Offset") {
+            // Stored in seconds
+            if let Ok(offset_secs) = offset_text.trim_matches('"').parse::<f64>() {
+                offset = offset_secs * 1000.0;
             }
         }
     }
@@ -194,21 +267,37 @@ pub fn parse_chart(text: &str) -> Result<SongChart, ChartParseError> {
         let sync_track_events = get_sync_track_parsed(song_section)?;
 
         // Map chart sync events
-        let mut notes = sync_track_events
+        for (pos, s, v, v2) in sync_track_events.iter() {
+            let value = match (*s, *v) {
+                ("B", 0) => return Err(ChartParseError::InvalidTempo { pos: *pos }),
+                ("B", bpm) => SyncEventType::Beat(bpm),
+                ("TS", numerator) => SyncEventType::TimeSignature(numerator, *v2),
+                _ => continue,
+            };
+
+            sync_track.events.push(SyncEvent {
+                pos: *pos,
+                pos_realtime: 0.0,
+                value,
+            });
+        }
+    }
+
+    // Parse global events (lyrics, phrases, sections)
+    if let Some(events_section) = mapped_sections.get("Events") {
+        let events = get_events_track_parsed(events_section)?;
+
+        let mut notes = events
             .iter()
-            .filter(|(_, s, _)| "B".eq(*s) || "TS".eq(*s))
-            .map(|(pos, s, v)| SyncEvent {
+            .filter(|(_, s, _)| "E".eq(*s))
+            .map(|(pos, _, text)| TextEvent {
                 pos: *pos,
                 pos_realtime: 0.0,
-                value: match s {
-                    &"B" => SyncEventType::Beat(*v),
-                    &"TS" => SyncEventType::TimeSignature(*v, None),
-                    _ => panic!("Should be unreached!"),
-                }
+                value: map_text_event(text),
             })
             .collect();
 
-        sync_track
+        event_track
             .events
             .append(&mut notes);
     }
@@ -237,23 +326,26 @@ pub fn parse_chart(text: &str) -> Result<SongChart, ChartParseError> {
                 // Map guitar events
                 let notes = guitar_track
                     .iter()
-                    .filter(|(_, s, v1, _)| ("N".eq(*s) && *v1 <= 7) || ("S".eq(*s) && *v1 == 2))
-                    .map(|(pos, s, v1, v2)| GuitarEvent {
-                        pos: *pos,
-                        pos_realtime: 0.0,
-                        length: *v2 as u64,
-                        length_realtime: 0.0,
-                        value: match (s, v1) {
-                            (&"N", 0..=4) => GuitarEventType::Note(*v1),
-                            (&"N", 5) => GuitarEventType::Forced,
-                            (&"N", 6) => GuitarEventType::Tap,
-                            (&"N", 7) => GuitarEventType::Open,
-                            (&"S", _) => GuitarEventType::Starpower,
-                            _ => panic!("Should be unreached!"),
-                        }
+                    .filter_map(|(pos, s, v1, v2)| {
+                        let value = match (*s, *v1) {
+                            ("N", 0..=4) => GuitarEventType::Note(*v1),
+                            ("N", 5) => GuitarEventType::Forced,
+                            ("N", 6) => GuitarEventType::Tap,
+                            ("N", 7) => GuitarEventType::Open,
+                            ("S", 2) => GuitarEventType::Starpower,
+                            _ => return None,
+                        };
+
+                        Some(GuitarEvent {
+                            pos: *pos,
+                            pos_realtime: 0.0,
+                            length: *v2 as u64,
+                            length_realtime: 0.0,
+                            value,
+                        })
                     })
                     .collect();
-                
+
                 let guitar_track = GuitarTrack {
                     name: track_name.to_string(),
                     events: notes,
@@ -266,7 +358,341 @@ pub fn parse_chart(text: &str) -> Result<SongChart, ChartParseError> {
 
     Ok(SongChart {
         resolution,
+        offset,
+        song_values,
         sync_track,
+        event_track,
         guitar_tracks,
     })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PraiseError;
+
+    fn round_trip(chart: &SongChart) -> SongChart {
+        let mut data = Vec::new();
+        chart.write(&mut data).unwrap();
+
+        parse_chart(&String::from_utf8(data).unwrap()).unwrap()
+    }
+
+    fn guitar_event(pos: u64, length: u64, value: GuitarEventType) -> GuitarEvent {
+        GuitarEvent {
+            pos,
+            pos_realtime: 0.0,
+            length,
+            length_realtime: 0.0,
+            value,
+        }
+    }
+
+    fn text_event(pos: u64, value: TextEventType) -> TextEvent {
+        TextEvent {
+            pos,
+            pos_realtime: 0.0,
+            value,
+        }
+    }
+
+    fn sync_event(pos: u64, value: SyncEventType) -> SyncEvent {
+        SyncEvent {
+            pos,
+            pos_realtime: 0.0,
+            value,
+        }
+    }
+
+    #[test]
+    fn parse_chart_sections() {
+        let text = "[Song]
+{
+  Name = \"Song\"
+  Offset = 0.25
+  Resolution = 192
+}
+[SyncTrack]
+{
+  0 = TS 4
+  0 = B 120000
+  768 = TS 6 3
+  768 = B 95500
+}
+[Events]
+{
+  0 = E \"section Intro\"
+  384 = E \"phrase_start\"
+  384 = E \"lyric Hel-\"
+  480 = E \"phrase_end\"
+}
+[ExpertSingle]
+{
+  192 = N 0 0
+  192 = N 5 0
+  384 = N 7 96
+  384 = S 2 192
+  576 = E solo
+}";
+
+        let chart = parse_chart(text).unwrap();
+
+        assert_eq!(192, chart.resolution);
+        assert_eq!(250.0, chart.offset);
+        assert_eq!(vec![(String::from("Name"), String::from("\"Song\""))], chart.song_values);
+        assert_eq!(
+            vec![
+                sync_event(0, SyncEventType::TimeSignature(4, None)),
+                sync_event(0, SyncEventType::Beat(120_000)),
+                sync_event(768, SyncEventType::TimeSignature(6, Some(3))),
+                sync_event(768, SyncEventType::Beat(95_500)),
+            ],
+            chart.sync_track.events
+        );
+        assert_eq!(
+            vec![
+                text_event(0, TextEventType::Section(String::from("Intro"))),
+                text_event(384, TextEventType::PhraseStart),
+                text_event(384, TextEventType::Lyric(String::from("Hel-"))),
+                text_event(480, TextEventType::PhraseEnd),
+            ],
+            chart.event_track.events
+        );
+        assert_eq!(
+            vec![GuitarTrack {
+                name: String::from("ExpertSingle"),
+                events: vec![
+                    guitar_event(192, 0, GuitarEventType::Note(0)),
+                    guitar_event(192, 0, GuitarEventType::Forced),
+                    guitar_event(384, 96, GuitarEventType::Open),
+                    guitar_event(384, 192, GuitarEventType::Starpower),
+                ],
+            }],
+            chart.guitar_tracks
+        );
+    }
+
+    #[test]
+    fn round_trip_chart() {
+        let mut expert_events = Vec::new();
+
+        for i in 0..32u64 {
+            expert_events.push(guitar_event(i * 240, (i % 3) * 120, GuitarEventType::Note((i % 5) as u32)));
+
+            if i % 4 == 0 {
+                expert_events.push(guitar_event(i * 240, 0, GuitarEventType::Tap));
+            }
+        }
+
+        expert_events.push(guitar_event(0, 1920, GuitarEventType::Starpower));
+
+        let mut chart = SongChart {
+            resolution: 480,
+            offset: -150.0,
+            song_values: vec![
+                (String::from("Name"), String::from("\"Song\"")),
+                (String::from("Charter"), String::from("\"Someone\"")),
+                (String::from("Difficulty"), String::from("3")),
+            ],
+            sync_track: SyncTrack {
+                events: vec![
+                    sync_event(0, SyncEventType::TimeSignature(4, None)),
+                    sync_event(0, SyncEventType::Beat(140_000)),
+                    sync_event(1920, SyncEventType::TimeSignature(7, Some(3))),
+                    sync_event(3840, SyncEventType::Beat(70_250)),
+                ],
+            },
+            event_track: EventTrack {
+                events: vec![
+                    text_event(0, TextEventType::Section(String::from("Verse 1"))),
+                    text_event(480, TextEventType::PhraseStart),
+                    text_event(480, TextEventType::Lyric(String::from("Hello"))),
+                    text_event(720, TextEventType::Lyric(String::from("world"))),
+                    text_event(960, TextEventType::PhraseEnd),
+                    text_event(7200, TextEventType::Other(String::from("end"))),
+                ],
+            },
+            guitar_tracks: vec![
+                GuitarTrack {
+                    name: String::from("ExpertSingle"),
+                    events: expert_events,
+                },
+                GuitarTrack {
+                    name: String::from("EasyDoubleBass"),
+                    events: vec![
+                        guitar_event(0, 480, GuitarEventType::Note(1)),
+                        guitar_event(960, 0, GuitarEventType::Open),
+                        guitar_event(960, 0, GuitarEventType::Forced),
+                    ],
+                },
+            ],
+        };
+
+        // Writer orders events by position
+        for guitar_track in chart.guitar_tracks.iter_mut() {
+            guitar_track.events.sort_by_key(|ev| ev.pos);
+        }
+
+        assert_eq!(chart, round_trip(&chart));
+    }
+
+    #[test]
+    fn round_trip_realtime_positions() {
+        let chart = SongChart {
+            resolution: 192,
+            offset: 0.0,
+            song_values: Vec::new(),
+            sync_track: SyncTrack {
+                events: vec![
+                    sync_event(0, SyncEventType::Beat(120_000)),
+                    sync_event(384, SyncEventType::Beat(240_000)),
+                ],
+            },
+            event_track: EventTrack {
+                events: Vec::new(),
+            },
+            guitar_tracks: vec![GuitarTrack {
+                name: String::from("HardSingle"),
+                events: vec![guitar_event(192, 384, GuitarEventType::Note(2))],
+            }],
+        };
+
+        let mut data = Vec::new();
+        chart.write(&mut data).unwrap();
+
+        let chart = SongChart::from_bytes(&data).unwrap();
+        let note = &chart.guitar_tracks[0].events[0];
+
+        assert_eq!(500.0, note.pos_realtime);
+        assert_eq!(750.0, note.length_realtime);
+    }
+
+    #[test]
+    fn reject_zero_tempo() {
+        for value in ["0", "fast"] {
+            let text = format!("[SyncTrack]\n{{\n  0 = B 120000\n  768 = B {}\n}}", value);
+
+            assert!(matches!(
+                parse_chart(&text),
+                Err(ChartParseError::InvalidTempo { pos: 768 })
+            ));
+        }
+    }
+
+    #[test]
+    fn reject_zero_resolution() {
+        let text = "[Song]\n{\n  Resolution = 0\n}\n[SyncTrack]\n{\n  0 = B 120000\n}";
+
+        assert!(matches!(
+            SongChart::from_bytes(text.as_bytes()),
+            Err(ChartParseError::InvalidResolution)
+        ));
+    }
+
+    #[test]
+    fn unordered_tempo_events() {
+        let text = "[Song]
+{
+  Resolution = 192
+}
+[SyncTrack]
+{
+  384 = B 240000
+  0 = B 120000
+}
+[HardSingle]
+{
+  576 = N 2 0
+}";
+
+        let chart = SongChart::from_bytes(text.as_bytes()).unwrap();
+
+        assert_eq!(1000.0, chart.sync_track.events[1].pos_realtime);
+        assert_eq!(1250.0, chart.guitar_tracks[0].events[0].pos_realtime);
+    }
+
+    #[test]
+    fn round_trip_chart_file() {
+        let text = "[Song]
+{
+  Offset = 0
+  Resolution = 192
+}
+[SyncTrack]
+{
+  0 = TS 4
+  768 = TS 6 3
+  768 = B 95500
+}
+[Events]
+{
+  0 = E \"section Intro\"
+}
+[ExpertSingle]
+{
+  192 = N 0 0
+  960 = N 1 96
+}
+";
+
+        let chart = SongChart::from_bytes(text.as_bytes()).unwrap();
+
+        // Time signatures are kept and default tempo isn't added
+        let mut data = Vec::new();
+        chart.write(&mut data).unwrap();
+        assert_eq!(text, String::from_utf8(data.clone()).unwrap());
+
+        let written_chart = SongChart::from_bytes(&data).unwrap();
+        assert_eq!(chart, written_chart);
+        assert_eq!(2000.0, written_chart.sync_track.events[2].pos_realtime);
+        assert_eq!(2628.0, written_chart.guitar_tracks[0].events[1].pos_realtime.round());
+    }
+
+    #[test]
+    fn reject_quoted_text() {
+        let mut chart = SongChart::from_bytes(b"[Events]\n{\n}").unwrap();
+        chart.event_track.events.push(text_event(0, TextEventType::Lyric(String::from("\"Hi\""))));
+
+        let mut data = Vec::new();
+        assert!(matches!(
+            chart.write(&mut data),
+            Err(PraiseError::ChartWrite(ChartWriteError::UnsupportedText { .. }))
+        ));
+    }
+
+    #[test]
+    fn round_trip_song_values() {
+        let text = "[Song]
+{
+  Name = \"Song\"
+  Artist = \"Artist\"
+  Offset = 0.5
+  Resolution = 192
+  Genre = \"rock\"
+  MusicStream = \"song.ogg\"
+}";
+
+        let chart = round_trip(&parse_chart(text).unwrap());
+
+        assert_eq!(500.0, chart.offset);
+        assert_eq!(192, chart.resolution);
+        assert_eq!(
+            vec![
+                (String::from("Name"), String::from("\"Song\"")),
+                (String::from("Artist"), String::from("\"Artist\"")),
+                (String::from("Genre"), String::from("\"rock\"")),
+                (String::from("MusicStream"), String::from("\"song.ogg\"")),
+            ],
+            chart.song_values
+        );
+
+        let mut chart = chart;
+        chart.song_values.push((String::from("Charter"), String::from("\"Line\nbreak\"")));
+
+        let mut data = Vec::new();
+        assert!(matches!(
+            chart.write(&mut data),
+            Err(PraiseError::ChartWrite(ChartWriteError::UnsupportedSongValue { .. }))
+        ));
+    }
 }
\ No newline at end of file
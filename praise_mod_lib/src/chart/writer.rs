@@ -0,0 +1,100 @@
+use crate::chart::*;
+use crate::PraiseError;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+impl SongChart {
+    pub fn write_to_path<T: AsRef<Path>>(&self, chart_path: T) -> Result<(), PraiseError> {
+        let chart_file = File::create(chart_path)?;
+        let mut writer = BufWriter::new(chart_file);
+
+        self.write(&mut writer)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    pub fn write<T: Write>(&self, chart_file: &mut T) -> Result<(), PraiseError> {
+        // Song metadata
+        writeln!(chart_file, "[Song]\n{{")?;
+        writeln!(chart_file, "  Offset = {}", self.offset / 1000.0)?;
+        writeln!(chart_file, "  Resolution = {}", self.resolution)?;
+
+        for (key, value) in self.song_values.iter() {
+            if !key.chars().all(|c| c.is_ascii_alphanumeric()) || value.contains(['\r', '\n']) {
+                return Err(ChartWriteError::UnsupportedSongValue { key: key.to_owned() }.into());
+            }
+
+            writeln!(chart_file, "  {} = {}", key, value)?;
+        }
+
+        writeln!(chart_file, "}}")?;
+
+        // Tempo + time signatures
+        let sync_events = self.sync_track
+            .events
+            .iter()
+            .map(|ev| (ev.pos, match ev.value {
+                SyncEventType::Beat(bpm) => format!("B {}", bpm),
+                SyncEventType::TimeSignature(num, Some(den)) => format!("TS {} {}", num, den),
+                SyncEventType::TimeSignature(num, None) => format!("TS {}", num),
+            }))
+            .collect();
+
+        write_section(chart_file, "SyncTrack", sync_events)?;
+
+        // Lyrics, phrases + sections
+        let text_events = self.event_track
+            .events
+            .iter()
+            .map(|ev| (ev.pos, match &ev.value {
+                TextEventType::PhraseStart => String::from("phrase_start"),
+                TextEventType::PhraseEnd => String::from("phrase_end"),
+                TextEventType::Lyric(text) => format!("lyric {}", text),
+                TextEventType::Section(text) => format!("section {}", text),
+                TextEventType::Other(text) => text.to_owned(),
+            }))
+            .map(|(pos, text)| match text.contains(['"', '\r', '\n']) {
+                // Text can't be escaped in .chart
+                true => Err(ChartWriteError::UnsupportedText { text }),
+                _ => Ok((pos, format!("E \"{}\"", text))),
+            })
+            .collect::<Result<_, _>>()?;
+
+        write_section(chart_file, "Events", text_events)?;
+
+        // Guitar/bass tracks
+        for guitar_track in self.guitar_tracks.iter() {
+            let guitar_events = guitar_track
+                .events
+                .iter()
+                .map(|ev| (ev.pos, match ev.value {
+                    GuitarEventType::Note(fret) => format!("N {} {}", fret, ev.length),
+                    GuitarEventType::Forced => format!("N 5 {}", ev.length),
+                    GuitarEventType::Tap => format!("N 6 {}", ev.length),
+                    GuitarEventType::Open => format!("N 7 {}", ev.length),
+                    GuitarEventType::Starpower => format!("S 2 {}", ev.length),
+                }))
+                .collect();
+
+            write_section(chart_file, &guitar_track.name, guitar_events)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn write_section<T: Write>(chart_file: &mut T, name: &str, mut events: Vec<(u64, String)>) -> Result<(), PraiseError> {
+    // Events must be in order of position (keeps order of events at same position)
+    events.sort_by_key(|(pos, _)| *pos);
+
+    writeln!(chart_file, "[{}]\n{{", name)?;
+
+    for (pos, event) in events.iter() {
+        writeln!(chart_file, "  {} = {}", pos, event)?;
+    }
+
+    writeln!(chart_file, "}}")?;
+    Ok(())
+}
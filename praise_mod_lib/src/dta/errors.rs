@@ -0,0 +1,22 @@
+use thiserror::Error as ThisError;
+
+#[derive(Debug, ThisError)]
+pub enum DtaParseError {
+    #[error("Can't open dta file because of \"{text}\"")]
+    CantOpenDtaFile {
+        text: String,
+    },
+    #[error("Unexpected \"{bracket}\" on line {line}")]
+    UnexpectedBracket {
+        bracket: char,
+        line: usize,
+    },
+    #[error("Missing closing bracket for node starting on line {line}")]
+    UnclosedNode {
+        line: usize,
+    },
+    #[error("Missing closing quote for string starting on line {line}")]
+    UnclosedString {
+        line: usize,
+    },
+}
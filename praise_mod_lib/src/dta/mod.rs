@@ -0,0 +1,7 @@
+mod errors;
+mod parser;
+mod songs_dta;
+
+pub use self::errors::*;
+pub use self::parser::*;
+pub use self::songs_dta::*;
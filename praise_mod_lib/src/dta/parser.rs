@@ -0,0 +1,353 @@
+use crate::dta::DtaParseError;
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DtaNode {
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Symbol(String),
+    Variable(String),
+    Array(Vec<DtaNode>), // ( )
+    Command(Vec<DtaNode>), // { }
+    Property(Vec<DtaNode>), // [ ]
+}
+
+impl DtaNode {
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            DtaNode::String(text) | DtaNode::Symbol(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            DtaNode::Integer(value) => Some(*value),
+            DtaNode::Float(value) => Some(*value as i64),
+            _ => None,
+        }
+    }
+
+    pub fn get_children(&self) -> &[DtaNode] {
+        match self {
+            DtaNode::Array(nodes) | DtaNode::Command(nodes) | DtaNode::Property(nodes) => nodes,
+            _ => &[],
+        }
+    }
+
+    // Finds child array with given key as first value (i.e. (name "Song"))
+    pub fn find_array(&self, key: &str) -> Option<&[DtaNode]> {
+        self.get_children()
+            .iter()
+            .filter_map(|node| match node {
+                DtaNode::Array(nodes) => Some(nodes.as_slice()),
+                _ => None,
+            })
+            .find(|nodes| nodes
+                .first()
+                .and_then(|n| n.as_text())
+                .map(|k| k.eq_ignore_ascii_case(key))
+                .unwrap_or(false))
+    }
+
+    // Gets first value of child array with given key
+    pub fn find_value(&self, key: &str) -> Option<&DtaNode> {
+        self.find_array(key)
+            .and_then(|nodes| nodes.get(1))
+    }
+}
+
+struct DtaTokenizer<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+}
+
+pub fn parse_dta(text: &str) -> Result<Vec<DtaNode>, DtaParseError> {
+    let mut tokenizer = DtaTokenizer {
+        chars: text.chars().peekable(),
+        line: 1,
+    };
+
+    tokenizer.parse_nodes(None)
+}
+
+impl<'a> DtaTokenizer<'a> {
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.chars.next();
+
+        if c == Some('\n') {
+            self.line += 1;
+        }
+
+        c
+    }
+
+    fn skip_line(&mut self) {
+        while let Some(c) = self.next_char() {
+            if c == '\n' {
+                break;
+            }
+        }
+    }
+
+    fn skip_block_comment(&mut self) {
+        let mut prev = '\0';
+
+        while let Some(c) = self.next_char() {
+            if prev == '*' && c == '/' {
+                break;
+            }
+
+            prev = c;
+        }
+    }
+
+    fn parse_nodes(&mut self, closing: Option<(char, usize)>) -> Result<Vec<DtaNode>, DtaParseError> {
+        let mut nodes = Vec::new();
+
+        while let Some(&c) = self.chars.peek() {
+            match c {
+                c if c.is_whitespace() => {
+                    self.next_char();
+                },
+                ';' => self.skip_line(), // Comment
+                '#' => self.skip_line(), // Preprocessor directive (i.e. #include)
+                '(' | '{' | '[' => {
+                    let start_line = self.line;
+                    self.next_char();
+
+                    let end = match c {
+                        '(' => ')',
+                        '{' => '}',
+                        _ => ']',
+                    };
+
+                    let children = self.parse_nodes(Some((end, start_line)))?;
+
+                    nodes.push(match c {
+                        '(' => DtaNode::Array(children),
+                        '{' => DtaNode::Command(children),
+                        _ => DtaNode::Property(children),
+                    });
+                },
+                ')' | '}' | ']' => {
+                    self.next_char();
+
+                    return match closing {
+                        Some((end, _)) if end == c => Ok(nodes),
+                        _ => Err(DtaParseError::UnexpectedBracket { bracket: c, line: self.line }),
+                    };
+                },
+                '"' => {
+                    let text = self.parse_quoted('"')?;
+                    nodes.push(DtaNode::String(text));
+                },
+                '\'' => {
+                    let text = self.parse_quoted('\'')?;
+                    nodes.push(DtaNode::Symbol(text));
+                },
+                _ => {
+                    let word = self.parse_word();
+
+                    if word.starts_with("/*") {
+                        if !word.ends_with("*/") || word.len() < 4 {
+                            self.skip_block_comment();
+                        }
+                        continue;
+                    }
+
+                    nodes.push(parse_word_node(word));
+                }
+            }
+        }
+
+        match closing {
+            Some((_, line)) => Err(DtaParseError::UnclosedNode { line }),
+            None => Ok(nodes),
+        }
+    }
+
+    fn parse_quoted(&mut self, quote: char) -> Result<String, DtaParseError> {
+        let start_line = self.line;
+        let mut text = String::new();
+
+        // Skip opening quote
+        self.next_char();
+
+        loop {
+            match self.next_char() {
+                Some(c) if c == quote => return Ok(text),
+                Some('\\') => match self.next_char() {
+                    Some('n') => text.push('\n'),
+                    Some('q') => text.push('"'),
+                    Some(c) => text.push(c),
+                    None => break,
+                },
+                Some(c) => text.push(c),
+                None => break,
+            }
+        }
+
+        Err(DtaParseError::UnclosedString { line: start_line })
+    }
+
+    fn parse_word(&mut self) -> String {
+        let mut word = String::new();
+
+        while let Some(&c) = self.chars.peek() {
+            if c.is_whitespace() || "(){}[]\";".contains(c) {
+                break;
+            }
+
+            word.push(c);
+            self.next_char();
+        }
+
+        word
+    }
+}
+
+fn parse_word_node(word: String) -> DtaNode {
+    if let Some(var) = word.strip_prefix('$') {
+        return DtaNode::Variable(var.to_owned());
+    }
+
+    if let Some(hex) = word.strip_prefix("0x") {
+        if let Ok(value) = i64::from_str_radix(hex, 16) {
+            return DtaNode::Integer(value);
+        }
+    }
+
+    if let Ok(value) = word.parse::<i64>() {
+        return DtaNode::Integer(value);
+    }
+
+    // Avoid treating symbols like "inf" as numbers
+    let is_numeric = word
+        .chars()
+        .next()
+        .map(|c| c.is_ascii_digit() || "-+.".contains(c))
+        .unwrap_or(false);
+
+    if is_numeric {
+        if let Ok(value) = word.parse::<f64>() {
+            return DtaNode::Float(value);
+        }
+    }
+
+    DtaNode::Symbol(word)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dta::SongsDta;
+
+    fn symbol(text: &str) -> DtaNode {
+        DtaNode::Symbol(text.to_string())
+    }
+
+    #[test]
+    fn parse_comments() {
+        let text = "; Line comment (ignored)
+(name 'Song') ; Trailing comment
+/* Block
+   comment (ignored) */
+/*single*/
+#include ../other.dta
+(year 2007)";
+
+        assert_eq!(
+            vec![
+                DtaNode::Array(vec![symbol("name"), symbol("Song")]),
+                DtaNode::Array(vec![symbol("year"), DtaNode::Integer(2007)]),
+            ],
+            parse_dta(text).unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_nested_arrays() {
+        let text = "(song (tracks ((drum (0 1)) (bass 2))) {set $var 1.5} [prop])";
+        let nodes = parse_dta(text).unwrap();
+
+        assert_eq!(
+            vec![DtaNode::Array(vec![
+                symbol("song"),
+                DtaNode::Array(vec![
+                    symbol("tracks"),
+                    DtaNode::Array(vec![
+                        DtaNode::Array(vec![
+                            symbol("drum"),
+                            DtaNode::Array(vec![DtaNode::Integer(0), DtaNode::Integer(1)]),
+                        ]),
+                        DtaNode::Array(vec![symbol("bass"), DtaNode::Integer(2)]),
+                    ]),
+                ]),
+                DtaNode::Command(vec![symbol("set"), DtaNode::Variable(String::from("var")), DtaNode::Float(1.5)]),
+                DtaNode::Property(vec![symbol("prop")]),
+            ])],
+            nodes
+        );
+
+        let tracks = nodes[0].find_array("tracks").unwrap();
+        assert_eq!(2, tracks[1].get_children().len());
+
+        assert!(matches!(parse_dta("(song (name"), Err(DtaParseError::UnclosedNode { line: 1 })));
+        assert!(matches!(parse_dta("(song ]"), Err(DtaParseError::UnexpectedBracket { bracket: ']', .. })));
+    }
+
+    #[test]
+    fn parse_quoted_strings() {
+        let text = "(name \"Say \\qHello\\q\\nWorld\") ('quoted symbol' \"(not an array)\")";
+
+        assert_eq!(
+            vec![
+                DtaNode::Array(vec![symbol("name"), DtaNode::String(String::from("Say \"Hello\"\nWorld"))]),
+                DtaNode::Array(vec![symbol("quoted symbol"), DtaNode::String(String::from("(not an array)"))]),
+            ],
+            parse_dta(text).unwrap()
+        );
+
+        assert!(matches!(parse_dta("\n(name \"Song)"), Err(DtaParseError::UnclosedString { line: 2 })));
+    }
+
+    #[test]
+    fn parse_songs_dta() {
+        let text = "(song1
+   (name \"First Song\")
+   (artist \"Artist \\qOne\\q\")
+   (song (name \"songs/song1/song1\") (tracks ((guitar (0 1)))))
+   (year_released 1999)
+   (preview 30000 60000)
+)
+; Second song
+(2000
+   (name \"Second Song\")
+   (artist 'Artist Two')
+   (album_name \"Album\")
+   (album_track_number 3)
+   (song (name \"songs\\\\other_dir\\\\second\"))
+)";
+
+        let songs_dta = SongsDta::from_bytes(text.as_bytes()).unwrap();
+        assert_eq!(2, songs_dta.songs.len());
+
+        let first = &songs_dta.songs[0];
+        assert_eq!("song1", first.shortname);
+        assert_eq!("song1", first.get_dir_name());
+        assert_eq!("song1.mid", first.get_midi_file_name());
+        assert_eq!(Some("Artist \"One\""), first.metadata.get("artist").map(|a| a.as_str()));
+        assert_eq!(Some("1999"), first.metadata.get("year").map(|y| y.as_str()));
+        assert_eq!(Some("60000"), first.metadata.get("preview_end_time").map(|p| p.as_str()));
+
+        let second = &songs_dta.songs[1];
+        assert_eq!("2000", second.shortname);
+        assert_eq!("other_dir", second.get_dir_name());
+        assert_eq!("second.mid", second.get_midi_file_name());
+        assert_eq!(Some("Artist Two"), second.metadata.get("artist").map(|a| a.as_str()));
+        assert_eq!(Some("3"), second.metadata.get("album_track").map(|t| t.as_str()));
+    }
+}
\ No newline at end of file
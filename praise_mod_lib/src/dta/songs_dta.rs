@@ -0,0 +1,165 @@
+use crate::dta::*;
+use crate::song::SongMeta;
+use std::collections::HashMap;
+use std::fs::read;
+use std::path::Path;
+
+#[derive(Clone, Debug)]
+pub struct DtaSong {
+    pub shortname: String,
+    pub song_path: Option<String>, // Path to song files without extension (i.e. songs/shortname/shortname)
+    pub metadata: HashMap<String, String>, // Values mapped to song.ini keys
+}
+
+#[derive(Debug)]
+pub struct SongsDta {
+    pub songs: Vec<DtaSong>,
+}
+
+impl SongsDta {
+    pub fn from_path<T: AsRef<Path>>(dta_path: T) -> Result<SongsDta, DtaParseError> {
+        let data = read(dta_path)
+            .map_err(|err| DtaParseError::CantOpenDtaFile {
+                text: err.to_string(),
+            })?;
+
+        SongsDta::from_bytes(&data)
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<SongsDta, DtaParseError> {
+        // Text is either utf8 or latin1
+        let text = match std::str::from_utf8(data) {
+            Ok(text) => text.to_owned(),
+            Err(_) => data
+                .iter()
+                .map(|b| *b as char)
+                .collect(),
+        };
+
+        let nodes = parse_dta(text.trim_start_matches('\u{feff}'))?;
+
+        // Each root array is a song, starting with shortname
+        let songs = nodes
+            .iter()
+            .filter_map(|node| match node {
+                DtaNode::Array(children) => children
+                    .first()
+                    .and_then(|n| match n {
+                        DtaNode::Integer(id) => Some(id.to_string()),
+                        n => n.as_text().map(|t| t.to_owned()),
+                    })
+                    .map(|shortname| DtaSong::from_node(shortname, node)),
+                _ => None,
+            })
+            .collect();
+
+        Ok(SongsDta {
+            songs,
+        })
+    }
+}
+
+impl DtaSong {
+    fn from_node(shortname: String, node: &DtaNode) -> DtaSong {
+        let mut metadata = HashMap::new();
+
+        let text_keys = [
+            ("name", "name"),
+            ("artist", "artist"),
+            ("album_name", "album"),
+            ("genre", "genre"),
+            ("author", "charter"),
+            ("song_id", "song_id"),
+        ];
+
+        for (dta_key, ini_key) in text_keys {
+            let value = node
+                .find_value(dta_key)
+                .and_then(|n| match n {
+                    DtaNode::Integer(value) => Some(value.to_string()),
+                    n => n.as_text().map(|t| t.to_owned()),
+                });
+
+            if let Some(value) = value {
+                metadata.insert(ini_key.to_owned(), value);
+            }
+        }
+
+        let number_keys = [
+            ("year_released", "year"),
+            ("album_track_number", "album_track"),
+            ("song_length", "song_length"),
+        ];
+
+        for (dta_key, ini_key) in number_keys {
+            if let Some(value) = node.find_value(dta_key).and_then(|n| n.as_integer()) {
+                metadata.insert(ini_key.to_owned(), value.to_string());
+            }
+        }
+
+        // (preview start_ms end_ms)
+        if let Some(preview) = node.find_array("preview") {
+            let times = preview
+                .iter()
+                .skip(1)
+                .filter_map(|n| n.as_integer())
+                .collect::<Vec<_>>();
+
+            if let Some(start) = times.first() {
+                metadata.insert(String::from("preview_start_time"), start.to_string());
+            }
+
+            if let Some(end) = times.get(1) {
+                metadata.insert(String::from("preview_end_time"), end.to_string());
+            }
+        }
+
+        // (song (name "songs/shortname/shortname") ...)
+        let song_path = node
+            .find_array("song")
+            .and_then(|song| song
+                .iter()
+                .skip(1)
+                .filter_map(|n| match n {
+                    DtaNode::Array(children) => Some(children),
+                    _ => None,
+                })
+                .find(|children| children
+                    .first()
+                    .and_then(|n| n.as_text())
+                    .map(|k| k.eq_ignore_ascii_case("name"))
+                    .unwrap_or(false)))
+            .and_then(|children| children.get(1))
+            .and_then(|n| n.as_text())
+            .map(|t| t.replace('\\', "/"));
+
+        DtaSong {
+            shortname,
+            song_path,
+            metadata,
+        }
+    }
+
+    pub fn to_song_meta(&self) -> SongMeta {
+        SongMeta::from_metadata(&self.metadata)
+    }
+
+    pub fn get_dir_name(&self) -> &str {
+        // Directory containing song files
+        self.song_path
+            .as_deref()
+            .and_then(|p| p.rsplit('/').nth(1))
+            .filter(|d| !d.is_empty())
+            .unwrap_or(&self.shortname)
+    }
+
+    pub fn get_midi_file_name(&self) -> String {
+        let base_name = self.song_path
+            .as_deref()
+            .and_then(|p| p.rsplit('/').next())
+            .filter(|n| !n.is_empty())
+            .unwrap_or(&self.shortname);
+
+        format!("{}.mid", base_name)
+    }
+}
@@ -0,0 +1,46 @@
+use crate::audio::{AudioReaderError, AudioWriterError};
+use crate::chart::{ChartParseError, ChartWriteError};
+use crate::dta::DtaParseError;
+use crate::image::ResizeImageError;
+use crate::midi::{MidiReadError, MidiWriteError};
+use crate::pack::{PackCreateError, PackExtractError};
+use crate::sng::SngReadError;
+use crate::song::SongMetaError;
+use crate::xml::XmlParseError;
+use std::io::Error as IOError;
+use thiserror::Error as ThisError;
+use zip::result::ZipError;
+
+#[derive(Debug, ThisError)]
+pub enum PraiseError {
+    #[error(transparent)]
+    AudioReader(#[from] AudioReaderError),
+    #[error(transparent)]
+    AudioWriter(#[from] AudioWriterError),
+    #[error(transparent)]
+    ChartParse(#[from] ChartParseError),
+    #[error(transparent)]
+    ChartWrite(#[from] ChartWriteError),
+    #[error(transparent)]
+    DtaParse(#[from] DtaParseError),
+    #[error(transparent)]
+    ResizeImage(#[from] ResizeImageError),
+    #[error(transparent)]
+    MidiRead(#[from] MidiReadError),
+    #[error(transparent)]
+    MidiWrite(#[from] MidiWriteError),
+    #[error(transparent)]
+    PackCreate(#[from] PackCreateError),
+    #[error(transparent)]
+    PackExtract(#[from] PackExtractError),
+    #[error(transparent)]
+    SngRead(#[from] SngReadError),
+    #[error(transparent)]
+    SongMeta(#[from] SongMetaError),
+    #[error(transparent)]
+    XmlParse(#[from] XmlParseError),
+    #[error(transparent)]
+    IO(#[from] IOError),
+    #[error(transparent)]
+    Zip(#[from] ZipError),
+}
@@ -10,9 +10,13 @@ pub fn resize_and_save_image(in_path: &Path, out_path: &Path, width: u32, height
     // Read file to bytes
     let data = read(in_path)
         .map_err(|e| ResizeImageError::CantLoadImageFromFile { text: e.to_string() })?;
-    
+
+    resize_and_save_image_from_bytes(&data, out_path, width, height)
+}
+
+pub fn resize_and_save_image_from_bytes(data: &[u8], out_path: &Path, width: u32, height: u32) -> Result<(), ResizeImageError> {
     // Open image
-    let png = load_from_memory(&data)
+    let png = load_from_memory(data)
         .map_err(|e| ResizeImageError::CantLoadImageFromMemory { text: e.to_string() })?;
 
     // Resize image
@@ -1,8 +1,15 @@
+mod errors;
+
 pub mod audio;
 pub mod chart;
+pub mod dta;
 pub mod image;
 pub mod midi;
 pub mod pack;
+pub mod sng;
 pub mod song;
 pub(crate) mod shared;
-pub mod xml;
\ No newline at end of file
+pub mod vfs;
+pub mod xml;
+
+pub use self::errors::*;
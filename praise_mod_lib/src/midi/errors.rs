@@ -0,0 +1,61 @@
+use thiserror::Error as ThisError;
+
+#[derive(Debug, ThisError)]
+pub enum MidiReadError {
+    #[error("Can't open midi file because of \"{text}\"")]
+    CantOpenMidiFile {
+        text: String,
+    },
+    #[error("Invalid midi header")]
+    InvalidHeader,
+    #[error("Unexpected end of midi data at offset {offset}")]
+    UnexpectedEndOfData {
+        offset: usize,
+    },
+    #[error("Invalid status byte 0x{status:02X} at offset {offset}")]
+    InvalidStatusByte {
+        status: u8,
+        offset: usize,
+    },
+    #[error("Missing status byte for running status at offset {offset}")]
+    MissingRunningStatus {
+        offset: usize,
+    },
+    #[error("Midi format {format} is not supported")]
+    UnsupportedFormat {
+        format: u16,
+    },
+    #[error("Invalid data byte 0x{value:02X} at offset {offset}")]
+    InvalidDataByte {
+        value: u8,
+        offset: usize,
+    },
+    #[error("Variable length value longer than 4 bytes at offset {offset}")]
+    InvalidVariableLength {
+        offset: usize,
+    },
+    #[error("Invalid length of {length} for meta event 0x{event_type:02X} at offset {offset}")]
+    InvalidMetaEventLength {
+        event_type: u8,
+        length: usize,
+        offset: usize,
+    },
+}
+
+#[derive(Debug, ThisError)]
+pub enum MidiWriteError {
+    #[error("Can't write midi file because of \"{text}\"")]
+    CantWriteMidiFile {
+        text: String,
+    },
+    #[error("Delta time of {delta} ticks at tick {pos} is too large for variable length value")]
+    DeltaTimeTooLarge {
+        delta: u64,
+        pos: u64,
+    },
+    #[error("Event data length of {length} at tick {pos} is too large for variable length value")]
+    EventDataTooLarge {
+        length: usize,
+        pos: u64,
+    },
+}
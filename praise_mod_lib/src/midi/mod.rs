@@ -1,7 +1,11 @@
+mod errors;
+mod parser;
 mod reader;
 mod shared;
 mod smf;
+mod writer;
 
+pub use self::errors::*;
 pub(crate) use self::reader::*;
 pub use self::shared::*;
 pub use self::smf::*;
\ No newline at end of file
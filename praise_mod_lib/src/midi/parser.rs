@@ -0,0 +1,287 @@
+use crate::midi::MidiReadError;
+use ghakuf::messages::*;
+use ghakuf::reader::Handler;
+
+struct SmfCursor<'a> {
+    data: &'a [u8],
+    pos: usize,
+    base_offset: usize, // Offset of data in file
+}
+
+impl<'a> SmfCursor<'a> {
+    fn new(data: &'a [u8], base_offset: usize) -> SmfCursor<'a> {
+        SmfCursor {
+            data,
+            pos: 0,
+            base_offset,
+        }
+    }
+
+    fn get_offset(&self) -> usize {
+        self.base_offset + self.pos
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn read_bytes(&mut self, count: usize) -> Result<&'a [u8], MidiReadError> {
+        if self.data.len() - self.pos < count {
+            return Err(MidiReadError::UnexpectedEndOfData {
+                offset: self.base_offset + self.data.len(),
+            });
+        }
+
+        let bytes = &self.data[self.pos..(self.pos + count)];
+        self.pos += count;
+
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, MidiReadError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn peek_u8(&self) -> Result<u8, MidiReadError> {
+        match self.data.get(self.pos) {
+            Some(b) => Ok(*b),
+            None => Err(MidiReadError::UnexpectedEndOfData {
+                offset: self.get_offset(),
+            }),
+        }
+    }
+
+    fn read_u16(&mut self) -> Result<u16, MidiReadError> {
+        let bytes = self.read_bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn read_u32(&mut self) -> Result<u32, MidiReadError> {
+        let bytes = self.read_bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn read_data_byte(&mut self) -> Result<u8, MidiReadError> {
+        // Data bytes never have high bit set
+        let offset = self.get_offset();
+        let b = self.read_u8()?;
+
+        match b & 0x80 {
+            0 => Ok(b),
+            _ => Err(MidiReadError::InvalidDataByte {
+                value: b,
+                offset,
+            }),
+        }
+    }
+
+    fn read_variable_length(&mut self) -> Result<u32, MidiReadError> {
+        let offset = self.get_offset();
+        let mut value = 0u32;
+
+        // Max of 4 bytes
+        for _ in 0..4 {
+            let b = self.read_u8()?;
+            value = (value << 7) | (b & 0x7F) as u32;
+
+            if b & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(MidiReadError::InvalidVariableLength {
+            offset,
+        })
+    }
+}
+
+pub(crate) fn read_smf(data: &[u8], handler: &mut dyn Handler) -> Result<(), MidiReadError> {
+    let mut cursor = SmfCursor::new(data, 0);
+
+    // Parse header chunk
+    let header_tag = cursor.read_bytes(4)
+        .map_err(|_| MidiReadError::InvalidHeader)?;
+
+    if header_tag != b"MThd" {
+        return Err(MidiReadError::InvalidHeader);
+    }
+
+    let header_size = cursor.read_u32()? as usize;
+    if header_size < 6 {
+        return Err(MidiReadError::InvalidHeader);
+    }
+
+    let format = cursor.read_u16()?;
+    let track_count = cursor.read_u16()?;
+    let time_base = cursor.read_u16()?;
+
+    // Format 2 tracks are independent sequences
+    if format > 1 {
+        return Err(MidiReadError::UnsupportedFormat {
+            format,
+        });
+    }
+
+    // SMPTE time division isn't supported
+    if time_base == 0 || time_base & 0x8000 != 0 {
+        return Err(MidiReadError::InvalidHeader);
+    }
+
+    // Skip any extra header data
+    cursor.read_bytes(header_size - 6)?;
+    handler.header(format, track_count, time_base);
+
+    // Parse track chunks
+    while !cursor.is_empty() {
+        let chunk_tag = cursor.read_bytes(4)?;
+        let chunk_size = cursor.read_u32()? as usize;
+        let chunk_offset = cursor.get_offset();
+        let chunk_data = cursor.read_bytes(chunk_size)?;
+
+        if chunk_tag != b"MTrk" {
+            // Skip unknown chunks
+            continue;
+        }
+
+        handler.track_change();
+        read_track(chunk_data, chunk_offset, handler)?;
+    }
+
+    Ok(())
+}
+
+fn read_track(data: &[u8], data_offset: usize, handler: &mut dyn Handler) -> Result<(), MidiReadError> {
+    let mut cursor = SmfCursor::new(data, data_offset);
+    let mut running_status: Option<u8> = None;
+
+    while !cursor.is_empty() {
+        let delta_time = cursor.read_variable_length()?;
+
+        let status = match cursor.peek_u8()? {
+            b if b & 0x80 != 0 => {
+                cursor.read_u8()?;
+                b
+            },
+            _ => match running_status {
+                Some(status) => status,
+                None => return Err(MidiReadError::MissingRunningStatus {
+                    offset: cursor.get_offset(),
+                }),
+            },
+        };
+
+        match status {
+            0xFF => {
+                // Meta event
+                let event_offset = cursor.get_offset() - 1;
+                let event_type = cursor.read_u8()?;
+                let length = cursor.read_variable_length()? as usize;
+                let event_data = cursor.read_bytes(length)?.to_vec();
+
+                if event_type == 0x51 && length != 3 {
+                    // Tempo is always 24-bit value
+                    return Err(MidiReadError::InvalidMetaEventLength {
+                        event_type,
+                        length,
+                        offset: event_offset,
+                    });
+                }
+
+                let event = get_meta_event(event_type);
+                handler.meta_event(delta_time, &event, &event_data);
+
+                if event == MetaEvent::EndOfTrack {
+                    break;
+                }
+            },
+            0xF0 | 0xF7 => {
+                // Sysex event
+                let length = cursor.read_variable_length()? as usize;
+                let event_data = cursor.read_bytes(length)?.to_vec();
+
+                let event = match status {
+                    0xF0 => SysExEvent::F0,
+                    _ => SysExEvent::F7,
+                };
+
+                handler.sys_ex_event(delta_time, &event, &event_data);
+            },
+            0x80..=0xEF => {
+                // Channel event
+                running_status = Some(status);
+
+                let ch = status & 0x0F;
+                let data_1 = cursor.read_data_byte()?;
+
+                let event = match status & 0xF0 {
+                    0x80 => MidiEvent::NoteOff { ch, note: data_1, velocity: cursor.read_data_byte()? },
+                    0x90 => MidiEvent::NoteOn { ch, note: data_1, velocity: cursor.read_data_byte()? },
+                    0xA0 => MidiEvent::PolyphonicKeyPressure { ch, note: data_1, velocity: cursor.read_data_byte()? },
+                    0xB0 => MidiEvent::ControlChange { ch, control: data_1, data: cursor.read_data_byte()? },
+                    0xC0 => MidiEvent::ProgramChange { ch, program: data_1 },
+                    0xD0 => MidiEvent::ChannelPressure { ch, pressure: data_1 },
+                    _ => {
+                        // Pitch bend (14-bit value centered at 0x2000)
+                        let data_2 = cursor.read_data_byte()?;
+                        let value = ((data_2 as i16) << 7 | data_1 as i16) - 0x2000;
+
+                        MidiEvent::PitchBendChange { ch, data: value }
+                    },
+                };
+
+                handler.midi_event(delta_time, &event);
+            },
+            _ => {
+                // System common/realtime messages aren't valid in files
+                return Err(MidiReadError::InvalidStatusByte {
+                    status,
+                    offset: cursor.get_offset() - 1,
+                });
+            },
+        }
+    }
+
+    Ok(())
+}
+
+fn get_meta_event(event_type: u8) -> MetaEvent {
+    match event_type {
+        0x00 => MetaEvent::SequenceNumber,
+        0x01 => MetaEvent::TextEvent,
+        0x02 => MetaEvent::CopyrightNotice,
+        0x03 => MetaEvent::SequenceOrTrackName,
+        0x04 => MetaEvent::InstrumentName,
+        0x05 => MetaEvent::Lyric,
+        0x06 => MetaEvent::Marker,
+        0x07 => MetaEvent::CuePoint,
+        0x20 => MetaEvent::MIDIChannelPrefix,
+        0x2F => MetaEvent::EndOfTrack,
+        0x51 => MetaEvent::SetTempo,
+        0x54 => MetaEvent::SMTPEOffset,
+        0x58 => MetaEvent::TimeSignature,
+        0x59 => MetaEvent::KeySignature,
+        0x7F => MetaEvent::SequencerSpecificMetaEvent,
+        _ => MetaEvent::Unknown { event_type },
+    }
+}
+
+pub(crate) fn get_meta_event_type(event: &MetaEvent) -> u8 {
+    match event {
+        MetaEvent::SequenceNumber => 0x00,
+        MetaEvent::TextEvent => 0x01,
+        MetaEvent::CopyrightNotice => 0x02,
+        MetaEvent::SequenceOrTrackName => 0x03,
+        MetaEvent::InstrumentName => 0x04,
+        MetaEvent::Lyric => 0x05,
+        MetaEvent::Marker => 0x06,
+        MetaEvent::CuePoint => 0x07,
+        MetaEvent::MIDIChannelPrefix => 0x20,
+        MetaEvent::EndOfTrack => 0x2F,
+        MetaEvent::SetTempo => 0x51,
+        MetaEvent::SMTPEOffset => 0x54,
+        MetaEvent::TimeSignature => 0x58,
+        MetaEvent::KeySignature => 0x59,
+        MetaEvent::SequencerSpecificMetaEvent => 0x7F,
+        MetaEvent::Unknown { event_type } => *event_type,
+    }
+}
@@ -1,48 +1,64 @@
 //use ghakuf::messages::MidiEvent;
 use ghakuf::messages::*;
 use ghakuf::reader::*;
+use crate::midi::MidiReadError;
+use crate::midi::parser::*;
 use crate::midi::shared::*;
 use crate::midi::smf::*;
+use log::warn;
 use std::collections::HashMap;
+use std::fs::read;
 use std::path::Path;
 
 #[derive(Clone, Copy, Debug)]
 struct PendingMidiNote {
     pos: u64,
-    channel: u8,
     velocity: u8
 }
 
 pub(crate) struct MidiReader {
+    options: MidiReadOptions,
     info: Option<MidiInfo>,
     current_track_index: i32,
     current_pos: u64,
-    pending_notes: [Option<PendingMidiNote>; 0x80],
+    pending_notes: [[Option<PendingMidiNote>; 0x80]; 0x10], // Channel, pitch
     current_track: Option<MidiTrack>,
+    conductor_track: Option<MidiTrack>,
     tracks: Vec<MidiTrack>,
     tempo_track: Vec<MidiTempo>,
+    time_signatures: Vec<MidiTimeSignature>,
+    key_signatures: Vec<MidiKeySignature>,
 }
 
 impl MidiReader {
-    pub fn from_path(path: &Path) -> Result<MidiReader, Box<dyn std::error::Error>> {
+    pub fn from_path(path: &Path, options: MidiReadOptions) -> Result<MidiReader, MidiReadError> {
+        let data = read(path)
+            .map_err(|err| MidiReadError::CantOpenMidiFile {
+                text: err.to_string(),
+            })?;
+
+        MidiReader::from_bytes(&data, options)
+    }
+
+    pub fn from_bytes(data: &[u8], options: MidiReadOptions) -> Result<MidiReader, MidiReadError> {
         let mut midi_reader = MidiReader {
+            options,
             info: None,
             current_track_index: -1,
             current_pos: 0,
-            pending_notes: [None; 0x80],
+            pending_notes: [[None; 0x80]; 0x10],
             current_track: None,
+            conductor_track: None,
             tracks: Vec::new(),
             tempo_track: Vec::new(),
+            time_signatures: Vec::new(),
+            key_signatures: Vec::new(),
         };
 
-        let mut reader = Reader::new(
-            &mut midi_reader,
-            &path)
-            .unwrap();
-        
-        reader.read().unwrap();
+        read_smf(data, &mut midi_reader)?;
 
-        midi_reader.finalize_track(); // Finalize last track
+        midi_reader.finalize_track(false); // Finalize last track
+        midi_reader.finalize_tempo();
         Ok(midi_reader)
     }
 
@@ -50,15 +66,18 @@ impl MidiReader {
 
     }*/
 
-    pub fn get_midi(&self) -> MidiFile {
-        let info = self.info.unwrap();
+    pub fn get_midi(&self) -> Result<MidiFile, MidiReadError> {
+        let info = self.info.ok_or(MidiReadError::InvalidHeader)?;
 
-        MidiFile {
+        Ok(MidiFile {
             format: info.format,
             ticks_per_quarter: info.ticks_per_quarter,
+            conductor_track: self.conductor_track.to_owned(),
             tracks: self.tracks.to_vec(),
             tempo: self.tempo_track.to_vec(),
-        }
+            time_signatures: self.time_signatures.to_vec(),
+            key_signatures: self.key_signatures.to_vec(),
+        })
     }
 }
 
@@ -73,72 +92,78 @@ impl Handler for MidiReader {
     fn meta_event(&mut self, delta_time: u32, event: &MetaEvent, data: &Vec<u8>) {
         self.update_pos(delta_time);
 
+        let pos = self.current_pos;
+        let track = match &mut self.current_track {
+            Some(track) => track,
+            None => return,
+        };
+
+        let get_text = || String::from_utf8_lossy(data).into_owned();
+
         match event {
-            MetaEvent::SequenceOrTrackName => {
+            MetaEvent::SequenceOrTrackName if track.name.is_none() => {
                 // Set track name
-                if let Some(track) = &mut self.current_track {
-                    if track.name.is_none() {
-                        track.name = String::from_utf8(data.to_owned()).ok();
-                    }
-                }
+                track.name = String::from_utf8(data.to_owned()).ok();
             },
-            MetaEvent::TextEvent => {                
-                if let Some(track) = &mut self.current_track {
-                    let text = String::from_utf8(data.to_owned())
-                        .unwrap_or(String::from(""));
-
-                    track.texts.push(MidiText {
-                        pos: self.current_pos,
-                        text: MidiTextType::Event(text),
-                    });
-                }
+            MetaEvent::TextEvent => {
+                track.texts.push(MidiText {
+                    pos,
+                    text: MidiTextType::Event(get_text()),
+                });
             },
-            MetaEvent::Lyric => {                
-                if let Some(track) = &mut self.current_track {
-                    let text = String::from_utf8(data.to_owned())
-                        .unwrap_or(String::from(""));
-
-                    track.texts.push(MidiText {
-                        pos: self.current_pos,
-                        text: MidiTextType::Lyric(text),
-                    });
-                }
+            MetaEvent::Lyric => {
+                track.texts.push(MidiText {
+                    pos,
+                    text: MidiTextType::Lyric(get_text()),
+                });
+            },
+            MetaEvent::Marker => {
+                track.texts.push(MidiText {
+                    pos,
+                    text: MidiTextType::Marker(get_text()),
+                });
+            },
+            MetaEvent::CuePoint => {
+                track.texts.push(MidiText {
+                    pos,
+                    text: MidiTextType::CuePoint(get_text()),
+                });
             },
             MetaEvent::SetTempo => {
-                if self.current_track_index > 0 {
-                    // Only parse tempo events from first track
-                    return;
-                }
-
-                let mpq = self.mpq_from_raw_tempo(data);
-
-                // Calculate bpm
-                let bpm = 60_000_000.0 / mpq as f64;
-                let mut pos_realtime = 0.0;
-
-                // Calculate realtime position
-                if let Some(last_tempo) = self.tempo_track.last() {
-                    let tpq = match &self.info {
-                        Some(info) => info.ticks_per_quarter,
-                        None => 480,
-                    };
-
-                    let delta_ticks = self.current_pos - last_tempo.pos;
-                    
-                    let delta_ms = (last_tempo.mpq as u64 * delta_ticks) as f64 / (1_000 * tpq as u32) as f64;
-                    pos_realtime = last_tempo.pos_realtime + delta_ms;
-                }
+                // Realtime positions are calculated after all tracks are read
+                let mpq = (data[0] as u32) << 16 | (data[1] as u32) << 8 | data[2] as u32;
 
                 self.tempo_track.push(MidiTempo {
-                    pos: self.current_pos,
-                    pos_realtime,
+                    pos,
+                    pos_realtime: 0.0,
                     mpq,
-                    bpm,
+                    bpm: 60_000_000.0 / mpq as f64,
+                });
+            },
+            MetaEvent::TimeSignature if data.len() >= 2 && data[1] < 8 => {
+                self.time_signatures.push(MidiTimeSignature {
+                    pos,
+                    numerator: data[0],
+                    denominator: 1 << data[1],
+                    clocks_per_click: data.get(2).copied().unwrap_or(24),
+                    thirty_seconds_per_quarter: data.get(3).copied().unwrap_or(8),
+                });
+            },
+            MetaEvent::KeySignature if data.len() >= 2 => {
+                self.key_signatures.push(MidiKeySignature {
+                    pos,
+                    sharps_flats: data[0] as i8,
+                    is_minor: data[1] != 0,
                 });
             },
+            MetaEvent::EndOfTrack => {},
             _ => {
-                // Skip text event
-                return;
+                // Keep raw data for anything else
+                track.meta.push(MidiMetaEvent {
+                    pos,
+                    event_type: get_meta_event_type(event),
+                    data: data.to_owned(),
+                });
             },
         }
     }
@@ -158,58 +183,66 @@ impl Handler for MidiReader {
             }
         };
 
-        if note_on && self.pending_notes[note as usize].is_some() {
-            // Double on note, ignore
-        } else if note_on {
-            // Set note
-            self.pending_notes[note as usize] = Some(PendingMidiNote {
-                pos: self.current_pos,
-                channel,
-                velocity,
-            })
-        } else if !note_on && self.pending_notes[note as usize].is_none() {
-            // Double off note, ignore
-        } else {
-            // Finalize pending note
-            let pending_note = self.pending_notes[note as usize].unwrap();
-            self.pending_notes[note as usize] = None;
+        let pending = self.pending_notes[channel as usize][note as usize];
 
-            let final_note = MidiReader::finalize_note(&pending_note, note, self.current_pos);
+        match (note_on, pending) {
+            (true, Some(pending_note)) => {
+                self.warn_note("Overlapping note", channel, note);
 
-            if let Some(track) = &mut self.current_track {
-                track.notes.push(final_note);
-            }
+                if self.options.overlap == NoteOverlapPolicy::EndPrevious {
+                    self.add_note(&pending_note, channel, note);
+                    self.pending_notes[channel as usize][note as usize] = Some(PendingMidiNote {
+                        pos: self.current_pos,
+                        velocity,
+                    });
+                }
+            },
+            (true, None) => {
+                // Set note
+                self.pending_notes[channel as usize][note as usize] = Some(PendingMidiNote {
+                    pos: self.current_pos,
+                    velocity,
+                });
+            },
+            (false, None) => {
+                // Double off note, ignore
+            },
+            (false, Some(pending_note)) => {
+                // Finalize pending note
+                self.pending_notes[channel as usize][note as usize] = None;
+                self.add_note(&pending_note, channel, note);
+            },
         }
     }
 
     fn sys_ex_event(&mut self, delta_time: u32, event: &SysExEvent, data: &Vec<u8>) {
         self.update_pos(delta_time);
 
+        let escaped = match event {
+            SysExEvent::F0 => false,
+            SysExEvent::F7 => true,
+            _ => return, // Skip unknown sysex
+        };
+
+        if let Some(track) = &mut self.current_track {
+            track.sysex.push(MidiSysEx {
+                pos: self.current_pos,
+                data: data.to_owned(),
+                escaped,
+            });
+        }
     }
 
     fn track_change(&mut self) {
-        if self.current_track_index == 0
-            && self.tempo_track.len() == 0 {
-            // No tempo changes found, default to 120bpm
-            self.tempo_track.push(MidiTempo {
-                pos: 0,
-                pos_realtime: 0.0,
-                mpq: 60_000_000 / 120,
-                bpm: 120.0,
-            })
-        }
-        else if self.current_track_index > 0 {
-            // Skip adding tempo track (has dedicated track instead)
-            self.finalize_track();
-        }
+        // First track is conductor if followed by other tracks
+        self.finalize_track(self.current_track_index == 0);
+
+        // Notes never carry over between tracks
+        self.pending_notes = [[None; 0x80]; 0x10];
 
         self.current_track_index += 1;
         self.current_pos = 0;
-        self.current_track = Some(MidiTrack {
-            name: None,
-            notes: Vec::new(),
-            texts: Vec::new(),
-        });
+        self.current_track = Some(MidiTrack::default());
     }
 }
 
@@ -218,75 +251,137 @@ impl MidiReader {
         self.current_pos += delta_time as u64;
     }
 
-    fn finalize_note(note: &PendingMidiNote, pitch: u8, end_pos: u64) -> MidiNote {
-        MidiNote {
-            pos: note.pos,
+    fn add_default_tempo(&mut self) {
+        self.tempo_track.insert(0, MidiTempo {
+            pos: 0,
             pos_realtime: 0.0,
-            length: end_pos - note.pos,
-            length_realtime: 0.0,
-            pitch,
-            channel: note.channel,
-            velocity: note.velocity,
+            mpq: 60_000_000 / 120,
+            bpm: 120.0,
+        });
+    }
+
+    fn add_note(&mut self, note: &PendingMidiNote, channel: u8, pitch: u8) {
+        let length = self.current_pos - note.pos;
+
+        if length == 0 {
+            self.warn_note("Zero-length note", channel, pitch);
+
+            if self.options.zero_length == ZeroLengthNotePolicy::Drop {
+                return;
+            }
+        }
+
+        if let Some(track) = &mut self.current_track {
+            track.notes.push(MidiNote {
+                pos: note.pos,
+                pos_realtime: 0.0,
+                length,
+                length_realtime: 0.0,
+                pitch,
+                channel,
+                velocity: note.velocity,
+            });
         }
     }
 
-    fn finalize_track(&mut self) {
+    fn warn_note(&self, message: &str, channel: u8, pitch: u8) {
+        let track_name = self.current_track
+            .as_ref()
+            .and_then(|t| t.name.as_deref())
+            .unwrap_or("");
+
+        warn!(
+            "{} (pitch {}, channel {}) at tick {} in track {} \"{}\"",
+            message,
+            pitch,
+            channel,
+            self.current_pos,
+            self.current_track_index,
+            track_name
+        );
+    }
+
+    fn finalize_track(&mut self, is_conductor: bool) {
         if self.current_track.is_none() {
             return;
         }
 
-        let mut track = self.current_track.take().unwrap();
-        self.current_track = None;
+        // Finalize notes missing off event at end of track
+        for channel in 0..0x10 {
+            for pitch in 0..0x80 {
+                if let Some(pending_note) = self.pending_notes[channel][pitch].take() {
+                    self.warn_note("Missing off event for note", channel as u8, pitch as u8);
+                    self.add_note(&pending_note, channel as u8, pitch as u8);
+                }
+            }
+        }
+
+        let mut track = match self.current_track.take() {
+            Some(track) => track,
+            None => return,
+        };
 
-        // Iterate over pending notes and finalize
-        // Note: This would only be needed if the input midi was missing off notes
-        for (i, note) in self.pending_notes.iter_mut().enumerate() {
-            if let Some(pending_note) = note {
-                // Finalize note
-                let final_note = MidiReader::finalize_note(pending_note, i as u8, self.current_pos);
-                track.notes.push(final_note);
+        // Sort by position, pitch then channel
+        track.notes.sort_by(|a, b| a.pos
+            .cmp(&b.pos)
+            .then(a.pitch.cmp(&b.pitch))
+            .then(a.channel.cmp(&b.channel)));
 
-                // Clear out old value
-                *note = None;
-            }
+        if is_conductor {
+            self.conductor_track = Some(track);
+        } else {
+            self.tracks.push(track);
         }
+    }
 
-        // Sort notes in track
-        track.notes.sort_by(|a, b| {
-            // Sort by position then pitch
-            if a.pos == b.pos {
-                a.pitch.partial_cmp(&b.pitch).unwrap()
-            } else {
-                a.pos.partial_cmp(&b.pos).unwrap()
-            }
-        });
+    fn finalize_tempo(&mut self) {
+        // Later event wins if multiple tempos at same position
+        self.tempo_track.sort_by_key(|t| t.pos);
+        self.tempo_track.reverse();
+        self.tempo_track.dedup_by_key(|t| t.pos);
+        self.tempo_track.reverse();
 
-        // Update realtime positions
-        let mut tempo_itr = self.tempo_track.iter().rev();
-        let mut current_tempo = tempo_itr.next().unwrap();
+        // No tempo at start, default to 120bpm
+        if self.tempo_track.first().map(|t| t.pos > 0).unwrap_or(true) {
+            self.add_default_tempo();
+        }
 
-        for note in track.notes.iter_mut().rev() {
-            let start_pos = note.pos;
-            let end_pos = start_pos + note.length;
+        // Calculate realtime positions
+        for i in 1..self.tempo_track.len() {
+            let pos = self.tempo_track[i].pos;
+            self.tempo_track[i].pos_realtime = self.calculate_realtime_ms(&self.tempo_track[i - 1], pos);
+        }
 
-            // Calculate realtime end position
-            while current_tempo.pos > end_pos {
-                current_tempo = tempo_itr.next().unwrap();
-            }
-            let end_pos_realtime = self.calculate_realtime_ms(current_tempo, end_pos);
+        self.time_signatures.sort_by_key(|t| t.pos);
+        self.key_signatures.sort_by_key(|k| k.pos);
 
-            // Calculate realtime start position
-            while current_tempo.pos > start_pos {
-                current_tempo = tempo_itr.next().unwrap();
-            }
-            let start_pos_realtime = self.calculate_realtime_ms(current_tempo, start_pos);
+        let mut tracks = std::mem::take(&mut self.tracks);
+        let mut conductor_track = self.conductor_track.take();
+
+        for track in tracks.iter_mut().chain(conductor_track.iter_mut()) {
+            for note in track.notes.iter_mut() {
+                let start_pos = note.pos;
+                let end_pos = start_pos + note.length;
+
+                let end_pos_realtime = self.calculate_realtime_ms(self.get_tempo_at(end_pos), end_pos);
+                let start_pos_realtime = self.calculate_realtime_ms(self.get_tempo_at(start_pos), start_pos);
 
-            note.pos_realtime = start_pos_realtime;
-            note.length_realtime = end_pos_realtime - start_pos_realtime;
+                note.pos_realtime = start_pos_realtime;
+                note.length_realtime = end_pos_realtime - start_pos_realtime;
+            }
         }
 
-        // Add to tracks
-        self.tracks.push(track);
+        self.tracks = tracks;
+        self.conductor_track = conductor_track;
+    }
+
+    fn get_tempo_at(&self, pos: u64) -> &MidiTempo {
+        // Tempo map always starts at 0
+        let index = self.tempo_track
+            .partition_point(|t| t.pos <= pos)
+            .saturating_sub(1);
+
+        &self.tempo_track[index]
     }
 
     fn calculate_realtime_ms(&self, tempo: &MidiTempo, pos_ticks: u64) -> f64 {
@@ -301,7 +396,4 @@ impl MidiReader {
         tempo.pos_realtime + delta_ms
     }
 
-    fn mpq_from_raw_tempo(&self, data: &Vec<u8>) -> u32 {
-        (data[0] as u32) << 16 | (data[1] as u32) << 8 as u32 | data[2] as u32
-    }
 }
\ No newline at end of file
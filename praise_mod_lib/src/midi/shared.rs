@@ -4,6 +4,27 @@ pub struct MidiInfo {
     pub ticks_per_quarter: u16, // Usually 480
 }
 
+// What to do when note starts while same pitch + channel is still held
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum NoteOverlapPolicy {
+    #[default]
+    IgnoreNew, // Keep held note, ignore new on event
+    EndPrevious, // End held note at new position and start new note
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ZeroLengthNotePolicy {
+    #[default]
+    Keep,
+    Drop,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MidiReadOptions {
+    pub overlap: NoteOverlapPolicy,
+    pub zero_length: ZeroLengthNotePolicy,
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct MidiNote {
     pub pos: u64,
@@ -23,10 +44,28 @@ pub struct MidiTempo {
     pub bpm: f64,
 }
 
+#[derive(Clone, Copy, Debug)]
+pub struct MidiTimeSignature {
+    pub pos: u64,
+    pub numerator: u8,
+    pub denominator: u8, // Actual value (i.e. 4 for quarter note)
+    pub clocks_per_click: u8,
+    pub thirty_seconds_per_quarter: u8,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct MidiKeySignature {
+    pub pos: u64,
+    pub sharps_flats: i8, // Negative = flats
+    pub is_minor: bool,
+}
+
 #[derive(Clone, Debug)]
 pub enum MidiTextType {
     Event(String),
-    Lyric(String)
+    Lyric(String),
+    Marker(String),
+    CuePoint(String),
 }
 
 #[derive(Clone, Debug)]
@@ -47,13 +86,32 @@ impl MidiText {
         match &self.text {
             MidiTextType::Lyric(text) => text,
             MidiTextType::Event(text) => text,
+            MidiTextType::Marker(text) => text,
+            MidiTextType::CuePoint(text) => text,
         }
     }
 }
 
 #[derive(Clone, Debug)]
+pub struct MidiSysEx {
+    pub pos: u64,
+    pub data: Vec<u8>,
+    pub escaped: bool, // F7 packet
+}
+
+// Meta event not otherwise parsed (i.e. copyright, sequencer specific)
+#[derive(Clone, Debug)]
+pub struct MidiMetaEvent {
+    pub pos: u64,
+    pub event_type: u8,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Debug, Default)]
 pub struct MidiTrack {
     pub name: Option<String>,
     pub notes: Vec<MidiNote>,
     pub texts: Vec<MidiText>,
+    pub sysex: Vec<MidiSysEx>,
+    pub meta: Vec<MidiMetaEvent>,
 }
\ No newline at end of file
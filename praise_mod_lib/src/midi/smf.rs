@@ -1,19 +1,65 @@
 use crate::midi::*;
+use crate::midi::writer::write_smf;
+use crate::vfs::FileSource;
+use std::fs::write;
+use std::io::Read;
 use std::path::Path;
 
 #[derive(Debug)]
 pub struct MidiFile {
     pub format: u16,
     pub ticks_per_quarter: u16,
+    pub conductor_track: Option<MidiTrack>, // First track of format 1 (events besides tempo + signatures)
     pub tracks: Vec<MidiTrack>,
     pub tempo: Vec<MidiTempo>,
+    pub time_signatures: Vec<MidiTimeSignature>,
+    pub key_signatures: Vec<MidiKeySignature>,
 }
 
 impl MidiFile {
-    pub fn from_path(midi_path: &Path) -> Result<MidiFile, Box<dyn std::error::Error>> {
-        let reader = MidiReader::from_path(midi_path)?;
-        let midi = reader.get_midi();
+    pub fn from_path(midi_path: &Path) -> Result<MidiFile, MidiReadError> {
+        let reader = MidiReader::from_path(midi_path, MidiReadOptions::default())?;
+        reader.get_midi()
+    }
+
+    pub fn from_source(source: &dyn FileSource, file_name: &str) -> Result<MidiFile, MidiReadError> {
+        let data = source
+            .read_file(file_name)
+            .map_err(|err| MidiReadError::CantOpenMidiFile {
+                text: err.to_string(),
+            })?;
+
+        MidiFile::from_bytes(&data)
+    }
+
+    pub fn from_reader<T: Read>(reader: &mut T) -> Result<MidiFile, MidiReadError> {
+        let mut data = Vec::new();
+        reader
+            .read_to_end(&mut data)
+            .map_err(|err| MidiReadError::CantOpenMidiFile {
+                text: err.to_string(),
+            })?;
+
+        MidiFile::from_bytes(&data)
+    }
 
-        Ok(midi)
+    pub fn from_bytes(data: &[u8]) -> Result<MidiFile, MidiReadError> {
+        MidiFile::from_bytes_with_options(data, MidiReadOptions::default())
     }
-}
\ No newline at end of file
+
+    pub fn from_bytes_with_options(data: &[u8], options: MidiReadOptions) -> Result<MidiFile, MidiReadError> {
+        let reader = MidiReader::from_bytes(data, options)?;
+        reader.get_midi()
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, MidiWriteError> {
+        write_smf(self)
+    }
+
+    pub fn write_to_path<T: AsRef<Path>>(&self, midi_path: T) -> Result<(), MidiWriteError> {
+        write(midi_path, self.to_bytes()?)
+            .map_err(|err| MidiWriteError::CantWriteMidiFile {
+                text: err.to_string(),
+            })
+    }
+}
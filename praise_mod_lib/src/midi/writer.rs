@@ -0,0 +1,423 @@
+use crate::midi::MidiWriteError;
+use crate::midi::shared::*;
+use crate::midi::smf::*;
+
+// Largest value that fits in 4 byte variable length value
+const MAX_VARIABLE_LENGTH: u64 = 0x0FFF_FFFF;
+
+// Events at same position are written in this order
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum EventOrder {
+    NoteOff, // Ends before next note starts
+    Meta,
+    NoteOn,
+    ZeroLengthNoteOff, // Must come after its own on event
+}
+
+struct TrackEvent {
+    pos: u64,
+    order: EventOrder,
+    data: Vec<u8>, // Status + data bytes
+}
+
+pub(crate) fn write_smf(midi: &MidiFile) -> Result<Vec<u8>, MidiWriteError> {
+    let mut data = Vec::new();
+
+    // Header chunk (always format 1 w/ dedicated tempo track)
+    data.extend_from_slice(b"MThd");
+    data.extend_from_slice(&6u32.to_be_bytes());
+    data.extend_from_slice(&1u16.to_be_bytes());
+    data.extend_from_slice(&((midi.tracks.len() + 1) as u16).to_be_bytes());
+    data.extend_from_slice(&midi.ticks_per_quarter.to_be_bytes());
+
+    write_track_chunk(&mut data, get_conductor_events(midi)?)?;
+
+    for track in midi.tracks.iter() {
+        write_track_chunk(&mut data, get_track_events(track)?)?;
+    }
+
+    Ok(data)
+}
+
+fn get_conductor_events(midi: &MidiFile) -> Result<Vec<TrackEvent>, MidiWriteError> {
+    let mut events = match &midi.conductor_track {
+        Some(track) => get_track_events(track)?,
+        None => Vec::new(),
+    };
+
+    for tempo in midi.tempo.iter() {
+        events.push(create_meta_event(tempo.pos, 0x51, &tempo.mpq.to_be_bytes()[1..])?);
+    }
+
+    for ts in midi.time_signatures.iter() {
+        // Denominator is stored as power of 2
+        let ts_data = [ts.numerator, ts.denominator.max(1).trailing_zeros() as u8, ts.clocks_per_click, ts.thirty_seconds_per_quarter];
+        events.push(create_meta_event(ts.pos, 0x58, &ts_data)?);
+    }
+
+    for ks in midi.key_signatures.iter() {
+        events.push(create_meta_event(ks.pos, 0x59, &[ks.sharps_flats as u8, ks.is_minor as u8])?);
+    }
+
+    Ok(events)
+}
+
+fn get_track_events(track: &MidiTrack) -> Result<Vec<TrackEvent>, MidiWriteError> {
+    let mut events = Vec::new();
+
+    if let Some(name) = &track.name {
+        events.push(create_meta_event(0, 0x03, name.as_bytes())?);
+    }
+
+    for text in track.texts.iter() {
+        let (event_type, text_data) = match &text.text {
+            MidiTextType::Event(t) => (0x01, t),
+            MidiTextType::Lyric(t) => (0x05, t),
+            MidiTextType::Marker(t) => (0x06, t),
+            MidiTextType::CuePoint(t) => (0x07, t),
+        };
+
+        events.push(create_meta_event(text.pos, event_type, text_data.as_bytes())?);
+    }
+
+    for meta in track.meta.iter() {
+        events.push(create_meta_event(meta.pos, meta.event_type, &meta.data)?);
+    }
+
+    for sysex in track.sysex.iter() {
+        let mut data = match sysex.escaped {
+            true => vec![0xF7],
+            false => vec![0xF0],
+        };
+        write_data_length(&mut data, sysex.data.len(), sysex.pos)?;
+        data.extend_from_slice(&sysex.data);
+
+        events.push(TrackEvent {
+            pos: sysex.pos,
+            order: EventOrder::Meta,
+            data,
+        });
+    }
+
+    for note in track.notes.iter() {
+        let channel = note.channel & 0x0F;
+        let pitch = note.pitch & 0x7F;
+
+        events.push(TrackEvent {
+            pos: note.pos,
+            order: EventOrder::NoteOn,
+            data: vec![0x90 | channel, pitch, note.velocity.clamp(1, 0x7F)], // 0 velocity is read as off
+        });
+
+        events.push(TrackEvent {
+            pos: note.pos + note.length,
+            order: match note.length {
+                0 => EventOrder::ZeroLengthNoteOff,
+                _ => EventOrder::NoteOff,
+            },
+            data: vec![0x80 | channel, pitch, 0],
+        });
+    }
+
+    Ok(events)
+}
+
+fn create_meta_event(pos: u64, event_type: u8, event_data: &[u8]) -> Result<TrackEvent, MidiWriteError> {
+    let mut data = vec![0xFF, event_type];
+    write_data_length(&mut data, event_data.len(), pos)?;
+    data.extend_from_slice(event_data);
+
+    Ok(TrackEvent {
+        pos,
+        order: EventOrder::Meta,
+        data,
+    })
+}
+
+fn write_data_length(data: &mut Vec<u8>, length: usize, pos: u64) -> Result<(), MidiWriteError> {
+    if length as u64 > MAX_VARIABLE_LENGTH {
+        return Err(MidiWriteError::EventDataTooLarge {
+            length,
+            pos,
+        });
+    }
+
+    write_variable_length(data, length as u32);
+    Ok(())
+}
+
+fn write_track_chunk(data: &mut Vec<u8>, mut events: Vec<TrackEvent>) -> Result<(), MidiWriteError> {
+    // Stable sort keeps original order of events w/ same position
+    events.sort_by_key(|e| (e.pos, e.order));
+
+    let mut track_data = Vec::new();
+    let mut current_pos = 0;
+    let mut running_status = None;
+
+    for event in events.iter() {
+        let delta = event.pos - current_pos;
+        if delta > MAX_VARIABLE_LENGTH {
+            return Err(MidiWriteError::DeltaTimeTooLarge {
+                delta,
+                pos: event.pos,
+            });
+        }
+
+        write_variable_length(&mut track_data, delta as u32);
+        current_pos = event.pos;
+
+        let status = event.data[0];
+
+        match status {
+            0x80..=0xEF if running_status == Some(status) => {
+                // Skip repeated status byte
+                track_data.extend_from_slice(&event.data[1..]);
+            },
+            0x80..=0xEF => {
+                running_status = Some(status);
+                track_data.extend_from_slice(&event.data);
+            },
+            _ => {
+                // Meta + sysex events cancel running status
+                running_status = None;
+                track_data.extend_from_slice(&event.data);
+            }
+        }
+    }
+
+    // End of track
+    track_data.extend_from_slice(&[0x00, 0xFF, 0x2F, 0x00]);
+
+    data.extend_from_slice(b"MTrk");
+    data.extend_from_slice(&(track_data.len() as u32).to_be_bytes());
+    data.extend_from_slice(&track_data);
+    Ok(())
+}
+
+fn write_variable_length(data: &mut Vec<u8>, value: u32) {
+    // 7 bits per byte, most significant first (value is checked against MAX_VARIABLE_LENGTH)
+    let mut bytes = vec![(value & 0x7F) as u8];
+    let mut value = value >> 7;
+
+    while value > 0 {
+        bytes.push(((value & 0x7F) as u8) | 0x80);
+        value >>= 7;
+    }
+
+    bytes.reverse();
+    data.extend_from_slice(&bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(midi: &MidiFile) -> MidiFile {
+        MidiFile::from_bytes(&midi.to_bytes().unwrap()).unwrap()
+    }
+
+    fn create_midi(tracks: Vec<MidiTrack>) -> MidiFile {
+        MidiFile {
+            format: 1,
+            ticks_per_quarter: 480,
+            conductor_track: None,
+            tracks,
+            tempo: vec![MidiTempo {
+                pos: 0,
+                pos_realtime: 0.0,
+                mpq: 500_000,
+                bpm: 120.0,
+            }],
+            time_signatures: Vec::new(),
+            key_signatures: Vec::new(),
+        }
+    }
+
+    fn create_track(name: &str, notes: Vec<MidiNote>) -> MidiTrack {
+        MidiTrack {
+            name: Some(name.to_string()),
+            notes,
+            ..Default::default()
+        }
+    }
+
+    fn note(pos: u64, length: u64, pitch: u8) -> MidiNote {
+        MidiNote {
+            pos,
+            pos_realtime: 0.0,
+            length,
+            length_realtime: 0.0,
+            pitch,
+            channel: 0,
+            velocity: 100,
+        }
+    }
+
+    fn get_notes(track: &MidiTrack) -> Vec<(u64, u64, u8)> {
+        track.notes
+            .iter()
+            .map(|n| (n.pos, n.length, n.pitch))
+            .collect()
+    }
+
+    #[test]
+    fn round_trip_track_order_and_names() {
+        let mut midi = create_midi(vec![
+            create_track("PART GUITAR", vec![note(0, 240, 96)]),
+            create_track("PART BASS", vec![note(480, 240, 97)]),
+            create_track("EVENTS", Vec::new()),
+        ]);
+        midi.conductor_track = Some(MidiTrack {
+            name: Some(String::from("song")),
+            ..Default::default()
+        });
+        midi.tempo.push(MidiTempo {
+            pos: 960,
+            pos_realtime: 0.0,
+            mpq: 400_000,
+            bpm: 150.0,
+        });
+        midi.time_signatures.push(MidiTimeSignature {
+            pos: 0,
+            numerator: 6,
+            denominator: 8,
+            clocks_per_click: 24,
+            thirty_seconds_per_quarter: 8,
+        });
+        midi.key_signatures.push(MidiKeySignature {
+            pos: 0,
+            sharps_flats: -3,
+            is_minor: true,
+        });
+
+        let read_midi = round_trip(&midi);
+
+        assert_eq!(1, read_midi.format);
+        assert_eq!(480, read_midi.ticks_per_quarter);
+        assert_eq!(Some("song"), read_midi.conductor_track.as_ref().and_then(|t| t.name.as_deref()));
+        assert_eq!(
+            vec![Some("PART GUITAR"), Some("PART BASS"), Some("EVENTS")],
+            read_midi.tracks.iter().map(|t| t.name.as_deref()).collect::<Vec<_>>()
+        );
+        assert_eq!(vec![(0, 240, 96)], get_notes(&read_midi.tracks[0]));
+        assert_eq!(vec![(480, 240, 97)], get_notes(&read_midi.tracks[1]));
+
+        assert_eq!(
+            vec![(0, 500_000), (960, 400_000)],
+            read_midi.tempo.iter().map(|t| (t.pos, t.mpq)).collect::<Vec<_>>()
+        );
+        assert_eq!(1000.0, read_midi.tempo[1].pos_realtime);
+
+        let ts = &read_midi.time_signatures[0];
+        assert_eq!((6, 8, 24, 8), (ts.numerator, ts.denominator, ts.clocks_per_click, ts.thirty_seconds_per_quarter));
+
+        let ks = &read_midi.key_signatures[0];
+        assert_eq!((-3, true), (ks.sharps_flats, ks.is_minor));
+    }
+
+    #[test]
+    fn round_trip_note_off_before_note_on() {
+        // Same pitch re-struck at end of previous note
+        let midi = create_midi(vec![
+            create_track("PART GUITAR", vec![note(0, 120, 96), note(120, 120, 96), note(240, 60, 96)]),
+        ]);
+
+        let data = midi.to_bytes().unwrap();
+        let read_midi = MidiFile::from_bytes(&data).unwrap();
+
+        assert_eq!(vec![(0, 120, 96), (120, 120, 96), (240, 60, 96)], get_notes(&read_midi.tracks[0]));
+
+        // Off event is written before on event at tick 120
+        let off_on = [0x80, 96, 0, 0x00, 0x90, 96, 100];
+        assert!(data.windows(off_on.len()).any(|w| w == off_on));
+    }
+
+    #[test]
+    fn round_trip_zero_length_notes() {
+        let midi = create_midi(vec![
+            create_track("PART GUITAR", vec![note(0, 0, 96), note(0, 120, 97), note(120, 0, 97)]),
+        ]);
+
+        let read_midi = round_trip(&midi);
+
+        assert_eq!(vec![(0, 0, 96), (0, 120, 97), (120, 0, 97)], get_notes(&read_midi.tracks[0]));
+    }
+
+    #[test]
+    fn round_trip_running_status() {
+        let midi = create_midi(vec![
+            create_track("PART GUITAR", vec![note(0, 120, 96), note(0, 120, 97), note(0, 120, 98)]),
+        ]);
+
+        let data = midi.to_bytes().unwrap();
+        let read_midi = MidiFile::from_bytes(&data).unwrap();
+
+        assert_eq!(vec![(0, 120, 96), (0, 120, 97), (0, 120, 98)], get_notes(&read_midi.tracks[0]));
+
+        // Status byte is only written for first on + off events
+        let on_events = [0x00, 0x90, 96, 100, 0x00, 97, 100, 0x00, 98, 100];
+        let off_events = [0x78, 0x80, 96, 0, 0x00, 97, 0, 0x00, 98, 0];
+        assert!(data.windows(on_events.len()).any(|w| w == on_events));
+        assert!(data.windows(off_events.len()).any(|w| w == off_events));
+    }
+
+    #[test]
+    fn round_trip_sysex_and_meta_events() {
+        let mut track = create_track("PART GUITAR", vec![note(0, 120, 96)]);
+        track.texts = vec![
+            MidiText { pos: 0, text: MidiTextType::Event(String::from("[idle]")) },
+            MidiText { pos: 60, text: MidiTextType::Lyric(String::from("Hey")) },
+            MidiText { pos: 120, text: MidiTextType::Marker(String::from("Verse")) },
+            MidiText { pos: 180, text: MidiTextType::CuePoint(String::from("Cue")) },
+        ];
+        track.sysex = vec![
+            MidiSysEx { pos: 0, data: vec![0x50, 0x53, 0x00, 0x00, 0x03, 0x01, 0x01, 0xF7], escaped: false },
+            MidiSysEx { pos: 240, data: vec![0x01, 0x02], escaped: true },
+        ];
+        track.meta = vec![
+            MidiMetaEvent { pos: 0, event_type: 0x02, data: b"Copyright".to_vec() },
+            MidiMetaEvent { pos: 300, event_type: 0x7F, data: vec![0x00, 0x01, 0x02] },
+        ];
+
+        let read_midi = round_trip(&create_midi(vec![track]));
+        let read_track = &read_midi.tracks[0];
+
+        assert_eq!(
+            vec![(0, "[idle]", false), (60, "Hey", true), (120, "Verse", false), (180, "Cue", false)],
+            read_track.texts.iter().map(|t| (t.pos, t.get_text().as_str(), t.is_lyric())).collect::<Vec<_>>()
+        );
+        assert!(matches!(read_track.texts[2].text, MidiTextType::Marker(_)));
+        assert!(matches!(read_track.texts[3].text, MidiTextType::CuePoint(_)));
+
+        assert_eq!(
+            vec![
+                (0, vec![0x50, 0x53, 0x00, 0x00, 0x03, 0x01, 0x01, 0xF7], false),
+                (240, vec![0x01, 0x02], true),
+            ],
+            read_track.sysex.iter().map(|s| (s.pos, s.data.clone(), s.escaped)).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![(0, 0x02, b"Copyright".to_vec()), (300, 0x7F, vec![0x00, 0x01, 0x02])],
+            read_track.meta.iter().map(|m| (m.pos, m.event_type, m.data.clone())).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn reject_delta_time_too_large() {
+        let midi = create_midi(vec![
+            create_track("PART GUITAR", vec![note(0, 120, 96), note(MAX_VARIABLE_LENGTH + 121, 120, 96)]),
+        ]);
+
+        assert!(matches!(
+            midi.to_bytes(),
+            Err(MidiWriteError::DeltaTimeTooLarge { delta, pos }) if delta == MAX_VARIABLE_LENGTH + 1 && pos == MAX_VARIABLE_LENGTH + 121
+        ));
+
+        // Largest delta still fits
+        let midi = create_midi(vec![
+            create_track("PART GUITAR", vec![note(MAX_VARIABLE_LENGTH, 0, 96)]),
+        ]);
+
+        assert_eq!(vec![(MAX_VARIABLE_LENGTH, 0, 96)], get_notes(&round_trip(&midi).tracks[0]));
+    }
+}
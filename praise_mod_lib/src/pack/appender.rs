@@ -0,0 +1,73 @@
+use crate::pack::*;
+use crate::shared::*;
+use crate::song::SongMeta;
+use crate::xml::*;
+use crate::PraiseError;
+use log::{error, info};
+use std::path::Path;
+
+pub fn add_to_pack(ops: &PackAddOptions) -> Result<PackReport, PraiseError> {
+    let pack_dir = Path::new(&ops.pack_path);
+    let master_path = pack_dir.join("master.xml");
+
+    if !master_path.exists() {
+        error!("No master.xml found in \"{}\"", &ops.pack_path);
+        return Err(PackCreateError::NoMasterXmlFound.into());
+    }
+
+    let (pack_name, pack_id, albums, songs) = match XmlFile::from_path(&master_path)?.track {
+        XmlTrack::Metadata { name, pack_id, albums, songs } => (name, pack_id, albums, songs),
+        _ => return Err(PackCreateError::NoMasterXmlFound.into()),
+    };
+
+    // Fallback to pack directory name (i.e. ep04)
+    let pack_id = match pack_id {
+        0 => get_pack_id_from_dir_name(pack_dir).unwrap_or_default(),
+        id => id,
+    };
+
+    if pack_id < 4 || pack_id > 98 {
+        return Err(PackCreateError::InvalidPackId { id: pack_id }.into());
+    }
+
+    info!(
+        "Adding songs to song pack \"{}\" with id {:03} ({} existing songs)",
+        &pack_name,
+        pack_id,
+        songs.len()
+    );
+
+    // Existing song files are left untouched unless the same song is added again
+    let existing_songs = songs
+        .into_iter()
+        .map(|song| {
+            let song_meta = SongMeta {
+                name: song.title,
+                artist: song.artist,
+                album: albums
+                    .get(song.album_idx as usize)
+                    .map(|a| a.to_owned())
+                    .unwrap_or_default(),
+                ..Default::default()
+            };
+
+            (song_meta, song.song_id, song.ratings)
+        })
+        .collect();
+
+    // Find CH song directories + song archives
+    let song_sources = find_song_sources(&ops.songs_path)?;
+    let song_paths = order_song_paths(&song_sources, &ops.songs_path, &ops.order)?;
+
+    let target = PackTarget {
+        output_dir: pack_dir.to_owned(),
+        song_sources: &song_sources,
+        songs_path: &ops.songs_path,
+        pack_name: &pack_name,
+        pack_id,
+        no_cache: ops.no_cache,
+        existing_songs,
+    };
+
+    write_songs_to_pack(target, &song_paths)
+}
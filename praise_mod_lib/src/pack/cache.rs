@@ -0,0 +1,123 @@
+use crate::PraiseError;
+use crate::shared::write_json_file;
+use crate::vfs::FileSource;
+use crate::xml::SongRatings;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::read;
+use std::path::{Path, PathBuf};
+use twox_hash::XxHash3_128;
+
+pub const CACHE_FILE_NAME: &str = "cache.json";
+
+// Bump when cache file format changes (conversion options are hashed with each song instead)
+const CACHE_VERSION: u32 = 4;
+
+// Conversion settings besides song files that change output
+#[derive(Debug, Serialize)]
+pub struct CacheOptions {
+    pub pack_id: u8,
+    pub song_id: u16,
+    pub offset_version: u32, // How song.ini delay + chart offset are applied
+    pub stem_version: u32, // How audio stems are mixed
+    pub ratings_version: u32, // How difficulty + bpm tiers are calculated
+    pub art_size: u32,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CachedSong {
+    pub source_path: PathBuf,
+    pub hash: String,
+    pub ratings: SongRatings,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ConversionCache {
+    pub version: u32,
+    pub songs: HashMap<u16, CachedSong>,
+}
+
+impl Default for ConversionCache {
+    fn default() -> ConversionCache {
+        ConversionCache {
+            version: CACHE_VERSION,
+            songs: HashMap::new(),
+        }
+    }
+}
+
+impl ConversionCache {
+    pub fn from_path<T: AsRef<Path>>(path: T) -> ConversionCache {
+        let path = path.as_ref();
+
+        if !path.exists() {
+            return ConversionCache::default();
+        }
+
+        let cache = read(path)
+            .map_err(|err| err.to_string())
+            .and_then(|data| serde_json::from_slice::<ConversionCache>(&data)
+                .map_err(|err| err.to_string()));
+
+        match cache {
+            Ok(cache) if cache.version == CACHE_VERSION => cache,
+            Ok(cache) => {
+                debug!("Ignoring conversion cache with version {} (expected {})", cache.version, CACHE_VERSION);
+                ConversionCache::default()
+            },
+            Err(err) => {
+                debug!("Ignoring conversion cache in \"{}\" ({})", path.display(), err);
+                ConversionCache::default()
+            }
+        }
+    }
+
+    pub fn get_song(&self, song_id: u16, hash: &str) -> Option<&CachedSong> {
+        self.songs
+            .get(&song_id)
+            .filter(|s| s.hash == hash)
+    }
+
+    pub fn write_to_file<T: AsRef<Path>>(&self, path: T) -> Result<(), PraiseError> {
+        write_json_file(self, path)
+    }
+}
+
+pub fn get_source_hash(source: &dyn FileSource, options: &CacheOptions) -> Result<String, PraiseError> {
+    let options_key = serde_json::to_vec(options)
+        .map_err(std::io::Error::from)?;
+
+    let mut hasher = XxHash3_128::new();
+    hash_field(&mut hasher, &options_key);
+
+    // Metadata stored outside of files (i.e. .sng)
+    if let Some(metadata) = source.get_metadata() {
+        let mut entries = metadata.iter().collect::<Vec<_>>();
+        entries.sort();
+
+        for (key, value) in entries {
+            hash_field(&mut hasher, key.as_bytes());
+            hash_field(&mut hasher, value.as_bytes());
+        }
+    }
+
+    let mut file_names = source.get_file_names();
+    file_names.sort();
+
+    for file_name in file_names.iter() {
+        let data = source.read_file(file_name)?;
+
+        hash_field(&mut hasher, file_name.as_bytes());
+        hash_field(&mut hasher, &data);
+    }
+
+    Ok(format!("{:032x}", hasher.finish_128()))
+}
+
+fn hash_field(hasher: &mut XxHash3_128, data: &[u8]) {
+    // Prefix length so adjacent fields can't run together
+    hasher.write(&(data.len() as u64).to_le_bytes());
+    hasher.write(data);
+}
@@ -1,4 +1,4 @@
-use crate::chart::SongChart;
+use crate::chart::{SongChart, SyncEventType};
 use crate::midi::MidiFile;
 
 #[derive(Debug)]
@@ -14,4 +14,29 @@ impl ChartFile {
             _ => false,
         }
     }
-}
\ No newline at end of file
+
+    pub fn get_offset(&self) -> f64 {
+        // Milliseconds (only .chart stores offset)
+        match &self {
+            ChartFile::Chart(chart) => chart.offset,
+            ChartFile::Midi(_) => 0.0,
+        }
+    }
+
+    pub fn get_tempo_map(&self) -> Vec<(f64, f64)> {
+        // (Position in ms, bpm)
+        match &self {
+            ChartFile::Chart(chart) => chart.get_tempo_events()
+                .iter()
+                .filter_map(|e| match e.value {
+                    SyncEventType::Beat(bpm) => Some((e.pos_realtime, bpm as f64 / 1000.0)),
+                    _ => None,
+                })
+                .collect(),
+            ChartFile::Midi(mid) => mid.tempo
+                .iter()
+                .map(|t| (t.pos_realtime, t.bpm))
+                .collect(),
+        }
+    }
+}
@@ -5,37 +5,113 @@ use crate::midi::*;
 use crate::pack::*;
 use crate::shared::*;
 use crate::song::*;
+use crate::vfs::*;
 use crate::xml::*;
+use crate::PraiseError;
 use log::{debug, error, info, warn};
 use rayon::prelude::*;
-use std::collections::HashMap;
-use std::error::Error;
-use std::fs::{copy, create_dir_all, read, remove_dir_all, write};
+use std::collections::{HashMap, HashSet};
+use std::fs::{copy, create_dir_all, read, remove_dir_all, rename, write};
 use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-pub fn create_pack(ops: &PackOptions) -> Result<(), Box<dyn Error>> {
+// Bump when handling changes so cached songs are converted again
+const OFFSET_HANDLING_VERSION: u32 = 1;
+const STEM_MIX_VERSION: u32 = 2;
+
+const ALBUM_ART_SIZE: u32 = 256;
+
+// Appended to song directory while converting
+const TEMP_SONG_DIR_SUFFIX: &str = ".tmp";
+
+#[derive(Clone, Copy, Debug)]
+enum StemType {
+    Backing,
+    Guitar,
+    Bass,
+}
+
+struct ConvertedSong {
+    meta: SongMeta,
+    ratings: SongRatings,
+    hash: String,
+}
+
+// Existing pack to write songs into
+pub(crate) struct PackTarget<'a> {
+    pub output_dir: PathBuf,
+    pub song_sources: &'a SongSources,
+    pub songs_path: &'a str,
+    pub pack_name: &'a str,
+    pub pack_id: u8,
+    pub no_cache: bool,
+    pub existing_songs: Vec<(SongMeta, u16, SongRatings)>, // Kept in master.xml unless replaced
+}
+
+pub const DEFAULT_PACK_NAME: &str = "Custom Song Pack";
+
+pub fn create_pack(ops: &PackOptions) -> Result<PackReport, PraiseError> {
+    let pack_name = match &ops.name {
+        Some(n) => n,
+        None => DEFAULT_PACK_NAME,
+    };
+
+    // Find CH song directories + song archives
+    let song_sources = find_song_sources(&ops.songs_path)?;
+    let song_paths = order_song_paths(&song_sources, &ops.songs_path, &ops.order)?;
+
+    create_pack_from_paths(ops, &song_sources, &song_paths, pack_name, ops.id)
+}
+
+pub(crate) fn create_pack_from_paths(ops: &PackOptions, song_sources: &SongSources, song_paths: &[PathBuf], pack_name: &str, pack_id: u8) -> Result<PackReport, PraiseError> {
+    if pack_id < 4 || pack_id > 98 {
+        return Err(PackCreateError::InvalidPackId { id: pack_id }.into());
+    }
+
+    let target = PackTarget {
+        output_dir: Path::new(&ops.output_path)
+            .join(format!("ep{:02}", pack_id)),
+        song_sources,
+        songs_path: &ops.songs_path,
+        pack_name,
+        pack_id,
+        no_cache: ops.no_cache,
+        existing_songs: Vec::new(),
+    };
+
+    write_songs_to_pack(target, song_paths)
+}
+
+pub(crate) fn write_songs_to_pack(target: PackTarget, song_paths: &[PathBuf]) -> Result<PackReport, PraiseError> {
     // Start timer
     let overall_start_time = Instant::now();
 
-    let song_paths = find_dirs_with_file_name(&ops.songs_path, "song.ini")?;
+    let PackTarget { output_dir, song_sources, songs_path, pack_name, pack_id, no_cache, existing_songs } = target;
+    let mut pack_report = PackReport::new(pack_name, pack_id, &output_dir);
+
+    // Load cached conversions from previous build
+    let cache_path = output_dir.join(CACHE_FILE_NAME);
+    let cache = match no_cache {
+        true => ConversionCache::default(),
+        false => ConversionCache::from_path(&cache_path),
+    };
+
     let song_count = song_paths.len();
     let digit_count: usize;
 
     match song_count {
         0 => {
-            error!("No songs found in \"{}\"", &ops.songs_path);
-            return Ok(())
+            error!("No songs found in \"{}\"", songs_path);
+            return Ok(pack_report)
+        },
+        c if c > MAX_SONGS_PER_PACK => {
+            return Err(PackCreateError::TooManySongs { count: c }.into());
         },
         1 => {
             digit_count = 0;
             info!("Found 1 song");
         },
-        1000..=usize::MAX => {
-            error!("Found {} songs which is over 1000 song limit", song_count);
-            return Ok(())
-        },
         _ => {
             // Update digit count
             digit_count = match song_count {
@@ -48,112 +124,204 @@ pub fn create_pack(ops: &PackOptions) -> Result<(), Box<dyn Error>> {
         }
     }
 
-    let pack_name = match &ops.name {
-        Some(n) => n,
-        None => "Custom Song Pack",
-    };
-    let pack_id = ops.id;
-
-    if pack_id < 4 || pack_id > 98 {
-        error!("Pack id value of {} is not valid (must be between 4-98)", pack_id);
-        return Ok(())
-    }
-
     info!(
         "Creating song pack with name \"{}\" and id {:03}",
         pack_name,
         pack_id
     );
 
-    let output_dir = Path::new(&ops.output_path)
-        .join(format!("ep{:02}", pack_id));
+    // Re-use ids from previous builds and assign new ids in song order
+    let id_map_path = output_dir.join(ID_MAP_FILE_NAME);
+    let mut id_map = SongIdMap::from_path(&id_map_path)?;
 
-    let global_song_index = Arc::new(Mutex::new(0));
-
-    // Iterate over song directories
-    let mut song_results: Vec<(SongMeta, u16)> = song_paths
-        .par_iter()
-        .enumerate()
-        .map(|(id, path)| {
-            let song_id = id as u16; // Use index as id
+    let song_keys = song_paths
+        .iter()
+        .map(|p| get_song_key(p, songs_path))
+        .collect::<Vec<_>>();
+
+    // Songs already in pack (i.e. re-running packadd) keep their id and are replaced in place
+    if !existing_songs.is_empty() {
+        let song_metas = song_paths
+            .iter()
+            .zip(song_keys.iter())
+            .map(|(path, key)| match id_map.songs.contains_key(key) {
+                true => None,
+                _ => read_song_meta(song_sources, path),
+            })
+            .collect::<Vec<_>>();
+
+        id_map.match_existing_songs(&song_keys, &song_metas, &existing_songs);
+    }
 
-            // Attempt to convert song
-            let song_meta = convert_song(path, pack_id, song_id, &output_dir);
+    let existing_ids = existing_songs
+        .iter()
+        .map(|(_, id, _)| *id)
+        .collect::<Vec<_>>();
+    let song_ids = id_map.assign_ids(&song_keys, &existing_ids)?;
 
-            // Update index
-            let i: i32;
-            {
-                // Increment song index
-                let mut song_index = global_song_index.lock().unwrap();
-                *song_index += 1;
+    let total_count = existing_ids
+        .iter()
+        .chain(song_ids.iter())
+        .collect::<HashSet<_>>()
+        .len();
 
-                // Assign value from global index to local
-                i = *song_index;
-            }
+    if total_count > MAX_SONGS_PER_PACK {
+        return Err(PackCreateError::TooManySongs { count: total_count }.into());
+    }
 
-            if song_meta.is_err() {
-                warn!(
-                    "({:0width$}/{}) Error parsing song in \"{}\", skipping",
-                    i,
-                    song_count,
-                    path.to_str().unwrap(),
-                    width = digit_count
-                );
-
-                // Clean up song data
-                let song_dir = output_dir
-                    .join(&format!("{:03}", song_id));
-
-                if song_dir.exists() {
-                    if let Err(err) = remove_dir_all(song_dir) {
-                        // Print error
-                        error!("{:?}", err);
-                    }
-                }
+    let global_song_index = AtomicUsize::new(0);
 
-                return None
-            }
+    // Iterate over song directories
+    let mut song_results: Vec<(Option<ConvertedSong>, SongReport)> = song_paths
+        .par_iter()
+        .zip(song_ids.par_iter())
+        .map(|(path, song_id)| {
+            let song_id = *song_id;
+            let mut song_report = SongReport::new(path, song_id);
 
-            let song_meta = song_meta.unwrap();
+            // Attempt to convert song
+            let song_start_time = Instant::now();
+            let song_meta = convert_song(song_sources, path, pack_id, song_id, &output_dir, &cache, &mut song_report);
+            song_report.timings.total = duration_to_ms(song_start_time.elapsed());
+
+            // Increment song index
+            let i = global_song_index.fetch_add(1, Ordering::SeqCst) + 1;
+
+            let converted_song = match song_meta {
+                Ok(res) => res,
+                Err(err) => {
+                    warn!(
+                        "({:0width$}/{}) Error parsing song in \"{}\" ({}), skipping",
+                        i,
+                        song_count,
+                        path.display(),
+                        err,
+                        width = digit_count
+                    );
+
+                    song_report.status = SongStatus::Failed;
+                    song_report.error = Some(err.to_string());
+
+                    // Song data is cleaned up after master.xml is written (existing songs are kept)
+                    return (None, song_report)
+                }
+            };
 
             info!(
-                "({:0width$}/{}) Successfully converted \"{} - {}\"",
+                "({:0width$}/{}) Successfully {} \"{} - {}\"",
                 i,
                 song_count,
-                &song_meta.name,
-                &song_meta.artist,
+                match song_report.status {
+                    SongStatus::Cached => "re-used cached",
+                    _ => "converted",
+                },
+                &converted_song.meta.name,
+                &converted_song.meta.artist,
                 width = digit_count
             );
 
-            return Some((song_meta, song_id))
+            (Some(converted_song), song_report)
         })
-        .filter(|res| res.is_some())
-        .map(|res| res.unwrap())
         .collect();
 
-    if song_results.len() == 0 {
-        error!("No songs found could be converted");
-        return Ok(());
+    // Sort songs by id
+    song_results.sort_by_key(|(_, report)| report.song_id);
+
+    // Keep existing songs not replaced by new conversions (failed conversions keep existing song)
+    let replaced_ids = song_results
+        .iter()
+        .filter(|(song, _)| song.is_some())
+        .map(|(_, report)| report.song_id)
+        .collect::<HashSet<_>>();
+
+    let failed_ids = song_results
+        .iter()
+        .filter(|(song, _)| song.is_none())
+        .map(|(_, report)| report.song_id)
+        .collect::<Vec<_>>();
+
+    let mut pack_songs = existing_songs
+        .into_iter()
+        .filter(|(_, id, _)| !replaced_ids.contains(id))
+        .collect::<Vec<_>>();
+
+    let mut new_cache = ConversionCache::default();
+    for (id, cached_song) in cache.songs.iter() {
+        if pack_songs.iter().any(|(_, song_id, _)| song_id == id) {
+            new_cache.songs.insert(*id, cached_song.to_owned());
+        }
     }
 
-    // Sort songs by id
-    song_results.sort_by(|a, b| a.1.cmp(&b.1));
+    for (song_result, song_report) in song_results.into_iter() {
+        if let Some(converted_song) = song_result {
+            pack_songs.push((converted_song.meta, song_report.song_id, converted_song.ratings));
+
+            new_cache.songs.insert(song_report.song_id, CachedSong {
+                source_path: song_report.source_path.to_owned(),
+                hash: converted_song.hash,
+                ratings: converted_song.ratings,
+                warnings: song_report.warnings.to_owned(),
+            });
+        }
+
+        pack_report.songs.push(song_report);
+    }
 
     // Add songs to builder
+    pack_songs.sort_by_key(|(_, id, _)| *id);
+
     let mut song_builder = XmlSongMetaBuilder::new(pack_name, pack_id);
-    for (meta, id) in song_results.iter() {
-        song_builder.add_song(meta, *id);
+    for (meta, id, ratings) in pack_songs.iter() {
+        song_builder.add_song(meta, *id, *ratings);
     }
 
-    // Write song pack xml
-    let xml_meta = song_builder.to_xml_meta();
-    xml_meta.write_to_file(&output_dir.join("master.xml"))?;
+    if pack_report.converted_count() == 0 {
+        error!("No songs found could be converted");
+    } else {
+        // Write song pack xml
+        let xml_meta = song_builder.to_xml_meta();
+        xml_meta.write_to_file(&output_dir.join("master.xml"))?;
+
+        // Remove output of failed + cached songs no longer in pack
+        let pack_ids = pack_songs
+            .iter()
+            .map(|(_, id, _)| *id)
+            .collect::<HashSet<_>>();
+
+        let removed_ids = failed_ids
+            .iter()
+            .chain(cache.songs.keys())
+            .filter(|id| !pack_ids.contains(id))
+            .collect::<HashSet<_>>();
+
+        for id in removed_ids {
+            let song_dir = output_dir
+                .join(&format!("{:03}", id));
+
+            if song_dir.exists() {
+                debug!("Removing song directory \"{}\"", song_dir.display());
+
+                if let Err(err) = remove_dir_all(song_dir) {
+                    error!("{:?}", err);
+                }
+            }
+        }
+
+        // Save ids + cache for next build
+        id_map.write_to_file(&id_map_path)?;
+        new_cache.write_to_file(&cache_path)?;
+
+        if pack_report.cached_count() > 0 {
+            info!("Re-used {} cached songs", pack_report.cached_count());
+        }
+    }
 
     // End timer
-    let total_time = format_duration(&overall_start_time.elapsed());
-    info!("Complete in {}", &total_time);
+    let elapsed = overall_start_time.elapsed();
+    pack_report.total_time = duration_to_ms(elapsed);
+    info!("Complete in {}", format_duration(&elapsed));
 
-    Ok(())
+    Ok(pack_report)
 }
 
 fn format_duration(duration: &Duration) -> String {
@@ -173,11 +341,14 @@ fn format_duration(duration: &Duration) -> String {
     )
 }
 
-fn convert_song(path: &Path, pack_id: u8, song_id: u16, output_dir: &Path) -> Result<SongMeta, Box<dyn Error>> {
-    debug!("Parsing song in \"{}\"", path.to_str().unwrap());
+fn convert_song(song_sources: &SongSources, path: &Path, pack_id: u8, song_id: u16, output_dir: &Path, cache: &ConversionCache, song_report: &mut SongReport) -> Result<ConvertedSong, PraiseError> {
+    debug!("Parsing song in \"{}\"", path.display());
 
-    let song_ini = path.join("song.ini");
-    let song_meta = SongMeta::from_path(&song_ini)?;
+    let stage_start_time = Instant::now();
+    let song_source = song_sources.open(path)?;
+    let song_source = song_source.as_ref();
+    let song_meta = SongMeta::from_source(song_source)?;
+    song_report.timings.metadata = duration_to_ms(stage_start_time.elapsed());
 
     debug!("Song Information\n\tTitle: {}\n\tArtist: {}\n\tAlbum: {}\n\tYear: {}\n\tPreview: {}",
         song_meta.name,
@@ -188,46 +359,143 @@ fn convert_song(path: &Path, pack_id: u8, song_id: u16, output_dir: &Path) -> Re
     );
 
     let full_song_id = format!("{:02}{:03}", pack_id, song_id);
-    let output_dir = output_dir
+    let song_dir = output_dir
         .join(&format!("{:03}", song_id));
 
-    if !output_dir.exists() {
-        // Create directory
-        create_dir_all(&output_dir)?;
+    // Check if song is unchanged since last build
+    let stage_start_time = Instant::now();
+    let cache_options = CacheOptions {
+        pack_id,
+        song_id,
+        offset_version: OFFSET_HANDLING_VERSION,
+        stem_version: STEM_MIX_VERSION,
+        ratings_version: RATINGS_VERSION,
+        art_size: ALBUM_ART_SIZE,
+    };
+
+    let hash = get_source_hash(song_source, &cache_options)?;
+    let cached_song = cache
+        .get_song(song_id, &hash)
+        .filter(|_| has_song_output_files(&song_dir, &full_song_id));
+    song_report.timings.cache = duration_to_ms(stage_start_time.elapsed());
+
+    if let Some(cached_song) = cached_song {
+        debug!("Re-using cached conversion for \"{}\"", path.display());
+
+        song_report.status = SongStatus::Cached;
+        song_report.warnings = cached_song.warnings.to_owned();
+
+        return Ok(ConvertedSong {
+            meta: song_meta,
+            ratings: cached_song.ratings,
+            hash,
+        });
+    }
+
+    // Convert into temp directory so existing song files are only replaced on success
+    let temp_dir = output_dir
+        .join(&format!("{:03}{}", song_id, TEMP_SONG_DIR_SUFFIX));
+
+    if temp_dir.exists() {
+        remove_dir_all(&temp_dir)?;
     }
 
+    create_dir_all(&temp_dir)?;
+
+    let song_ratings = match convert_song_files(song_source, &temp_dir, &full_song_id, &song_meta, song_report) {
+        Ok(song_ratings) => song_ratings,
+        Err(err) => {
+            if let Err(err) = remove_dir_all(&temp_dir) {
+                error!("{:?}", err);
+            }
+
+            return Err(err);
+        }
+    };
+
+    if song_dir.exists() {
+        remove_dir_all(&song_dir)?;
+    }
+
+    rename(&temp_dir, &song_dir)?;
+
+    Ok(ConvertedSong {
+        meta: song_meta,
+        ratings: song_ratings,
+        hash,
+    })
+}
+
+fn convert_song_files(song_source: &dyn FileSource, output_dir: &Path, full_song_id: &str, song_meta: &SongMeta, song_report: &mut SongReport) -> Result<SongRatings, PraiseError> {
     // Convert chart
-    convert_song_chart(path, &output_dir, &full_song_id)?;
+    let stage_start_time = Instant::now();
+    let chart_file = read_chart_file(song_source)?;
+
+    // Positive offset delays notes, negative offset delays audio instead
+    let offset = song_meta.delay.unwrap_or_default() as f64 + chart_file.get_offset();
+    let (note_offset, audio_padding) = match offset.round() as i64 {
+        o if o < 0 => (0, (-o) as u64),
+        o => (o as u64, 0),
+    };
+
+    if offset.round() != 0.0 {
+        debug!("Applying offset of {}ms", offset.round());
+    }
+
+    let song_ratings = convert_song_chart(&chart_file, output_dir, full_song_id, song_meta, note_offset)?;
+    song_report.timings.chart = duration_to_ms(stage_start_time.elapsed());
 
     // Copy art
-    convert_song_art(path, &output_dir, &full_song_id)?;
+    let stage_start_time = Instant::now();
+    convert_song_art(song_source, output_dir, full_song_id, song_report)?;
+    song_report.timings.art = duration_to_ms(stage_start_time.elapsed());
 
     // Convert audio
-    convert_song_audio(path, &output_dir, &full_song_id, &song_meta)?;
+    let stage_start_time = Instant::now();
+    convert_song_audio(song_source, output_dir, full_song_id, song_meta, audio_padding, song_report)?;
+    song_report.timings.audio = duration_to_ms(stage_start_time.elapsed());
 
-    Ok(song_meta)
+    Ok(song_ratings)
 }
 
-fn convert_song_chart(path: &Path, output_dir: &Path, full_song_id: &str) -> Result<(), Box<dyn Error>> {
-    let mut song_chart_path = path.join("notes.chart");
-    let chart_file;
+fn has_song_output_files(output_dir: &Path, full_song_id: &str) -> bool {
+    let mut file_names = vec![
+        format!("GPM{}.dpo", full_song_id),
+        format!("GPP{}.dpo", full_song_id),
+        format!("GPL{}.xml", full_song_id),
+    ];
 
-    if song_chart_path.exists() {
-        let song_chart = SongChart::from_path(&song_chart_path)?;
-        chart_file = ChartFile::Chart(song_chart);
-    } else {
-        // Chart not found, try mid
-        song_chart_path = path.join("notes.mid");
+    for i in 0..4 {
+        file_names.push(format!("GPG{}_{}.dpo", full_song_id, i));
+        file_names.push(format!("GPG{}_{}.xml", full_song_id, i));
+        file_names.push(format!("GPB{}_{}.xml", full_song_id, i));
+    }
 
-        if !song_chart_path.exists() {
-            error!("No chart in either .chart or .mid format found");
-        }
+    for i in 0..2 {
+        file_names.push(format!("GPB{}_{}.dpo", full_song_id, i));
+    }
+
+    file_names
+        .iter()
+        .all(|f| output_dir.join(f).exists())
+}
+
+fn read_chart_file(song_source: &dyn FileSource) -> Result<ChartFile, PraiseError> {
+    if song_source.contains_file("notes.chart") {
+        let song_chart = SongChart::from_source(song_source, "notes.chart")?;
+        return Ok(ChartFile::Chart(song_chart));
+    }
 
-        // TODO: Throw custom error instead
-        let mid = MidiFile::from_path(&song_chart_path)?;
-        chart_file = ChartFile::Midi(mid);
+    // Chart not found, try mid
+    if !song_source.contains_file("notes.mid") {
+        return Err(PackCreateError::NoChartFound.into());
     }
 
+    let mid = MidiFile::from_source(song_source, "notes.mid")?;
+    Ok(ChartFile::Midi(mid))
+}
+
+fn convert_song_chart(chart_file: &ChartFile, output_dir: &Path, full_song_id: &str, song_meta: &SongMeta, note_offset: u64) -> Result<SongRatings, PraiseError> {
     let instruments = [
         XmlTrackType::Guitar,
         XmlTrackType::Bass,
@@ -241,13 +509,16 @@ fn convert_song_chart(path: &Path, output_dir: &Path, full_song_id: &str) -> Res
         XmlTrackDifficulty::Expert,
     ];
 
+    let mut guitar_tracks = Vec::new();
+
     for ins_type in &instruments {
         // Parse vocals track
         if *ins_type == XmlTrackType::Vocals {
-            let xml_writer = match &chart_file {
+            let mut xml_writer = match chart_file {
                 ChartFile::Chart(chart) => XmlFile::from_chart(chart, *ins_type, None),
                 ChartFile::Midi(mid) => XmlFile::from_midi(mid, *ins_type, None)
             };
+            xml_writer.offset_events(note_offset);
 
             let track_name = format!(
                 "GPL{}.xml",
@@ -261,11 +532,18 @@ fn convert_song_chart(path: &Path, output_dir: &Path, full_song_id: &str) -> Res
 
         // Parse guitar/bass tracks
         for (i, diff) in gtr_difficulties.iter().enumerate() {
-            let xml_writer = match &chart_file {
+            let mut xml_writer = match chart_file {
                 ChartFile::Chart(chart) => XmlFile::from_chart(chart, *ins_type, Some(*diff)),
                 ChartFile::Midi(mid) => XmlFile::from_midi(mid, *ins_type, Some(*diff))
             };
 
+            // Keep guitar notes for difficulty ratings (before offset so timing matches tempo map)
+            if let (XmlTrackType::Guitar, XmlTrack::GuitarBass(beats)) = (ins_type, &xml_writer.track) {
+                guitar_tracks.push(beats.to_owned());
+            }
+
+            xml_writer.offset_events(note_offset);
+
             let track_name = format!(
                 "GP{}{}_{}.xml",
                 match &ins_type {
@@ -282,31 +560,39 @@ fn convert_song_chart(path: &Path, output_dir: &Path, full_song_id: &str) -> Res
         }
     }
 
-    Ok(())
-}
+    let song_ratings = SongRatings::new(
+        song_meta.diff_guitar,
+        &guitar_tracks.iter().collect::<Vec<_>>(),
+        &chart_file.get_tempo_map(),
+    );
 
-fn convert_song_art(path: &Path, output_dir: &Path, full_song_id: &str) -> Result<(), Box<dyn Error>> {
-    let mut album_art_path = path.join("album.png");
-    if !album_art_path.exists() {
-        // Fallback to .jpg
-        album_art_path = path.join("album.jpg");
+    Ok(song_ratings)
+}
 
-        if !album_art_path.exists() {
-            info!("No album art found");
-            return Ok(());
+fn convert_song_art(song_source: &dyn FileSource, output_dir: &Path, full_song_id: &str, song_report: &mut SongReport) -> Result<(), PraiseError> {
+    let album_art_data = match song_source.read_file("album.png") {
+        Ok(data) => data,
+        Err(_) => match song_source.read_file("album.jpg") {
+            // Fallback to .jpg
+            Ok(data) => data,
+            Err(_) => {
+                info!("No album art found");
+                song_report.add_warning("No album art found");
+                return Ok(());
+            }
         }
-    }
+    };
 
     // Copy album art to gp song directory
     let gp_art_file_path = output_dir.join(format!("GPC{}.png", full_song_id));
 
     // Resize image
-    let resize_res = resize_and_save_image(&album_art_path, &gp_art_file_path, 256, 256);
+    let resize_res = resize_and_save_image_from_bytes(&album_art_data, &gp_art_file_path, ALBUM_ART_SIZE, ALBUM_ART_SIZE);
 
     if resize_res.is_err() {
         let error = resize_res.unwrap_err();
         error!("{}", error);
-        return Err(Box::new(error));
+        return Err(error.into());
     }
 
     // TODO: Copy GPK art too
@@ -323,44 +609,99 @@ fn is_file_preview<T: AsRef<Path>>(file_path: T, ext: &str) -> bool {
     }
 }
 
-fn convert_song_audio(path: &Path, output_dir: &Path, full_song_id: &str, song_meta: &SongMeta) -> Result<(), Box<dyn Error>> {
-    let ogg_paths = get_files_in_dir(path, Some(&"ogg"))?;
+fn is_file_stem_name<T: AsRef<Path>>(file_path: T, name: &str) -> bool {
+    match file_path.as_ref().file_stem() {
+        Some(f_stem) => match f_stem.to_str() {
+            Some(f_stem_str) => f_stem_str.eq_ignore_ascii_case(name),
+            None => false,
+        },
+        None => false,
+    }
+}
+
+fn get_stem_type<T: AsRef<Path>>(file_path: T, has_bass_stem: bool) -> StemType {
+    let file_path = file_path.as_ref();
+
+    if is_file_stem_name(file_path, "guitar") {
+        StemType::Guitar
+    } else if is_file_stem_name(file_path, "bass") {
+        StemType::Bass
+    } else if is_file_stem_name(file_path, "rhythm") {
+        // Rhythm is used for bass charts when no dedicated bass part exists
+        // Otherwise it's rhythm guitar, which goes in backing so it isn't muted when lead guitar misses
+        match has_bass_stem {
+            true => StemType::Backing,
+            _ => StemType::Bass,
+        }
+    } else {
+        StemType::Backing
+    }
+}
+
+fn convert_song_audio(song_source: &dyn FileSource, output_dir: &Path, full_song_id: &str, song_meta: &SongMeta, audio_padding: u64, song_report: &mut SongReport) -> Result<(), PraiseError> {
+    let audio_paths = song_source.get_file_names()
+        .into_iter()
+        .map(PathBuf::from)
+        .filter(|p| is_supported_audio_file(p))
+        .collect::<Vec<PathBuf>>();
 
     let mut ogg_preview_path = None;
-    let ogg_stem_paths = ogg_paths
+    let ogg_stem_paths = audio_paths
         .iter()
         .filter(|p| {
             if is_file_preview(p, "ogg") {
                 ogg_preview_path = Some(*p);
                 false
             } else {
-                true
+                // Skip previews in other formats too
+                !is_file_stem_name(p, "preview")
             }
         })
         .collect::<Vec<&PathBuf>>();
     
+    let has_bass_stem = ogg_stem_paths
+        .iter()
+        .any(|p| is_file_stem_name(p, "bass"));
+
     let gp_backing_file_path = output_dir.join(format!("GPM{}.dpo", full_song_id));
     let gp_preview_file_path = output_dir.join(format!("GPP{}.dpo", full_song_id));
 
     match ogg_stem_paths.len() {
-        0 => return Err(Box::new(PackCreateError::NoAudioFound)),
+        0 => return Err(PackCreateError::NoAudioFound.into()),
         /* 1 => {
             // Only single stem found, no need for re-encoding
             let backing_path = ogg_stem_paths[0];
             ogg_to_dpo(backing_path, &gp_backing_file_path)?;
         }, */
         _ => {
-            // Read each ogg stem (initial metadata)
-            let mut ogg_stems: Vec<OggReader> = ogg_stem_paths
-                .iter()
-                .map(|p| OggReader::from_path(p))
-                .filter_map(Result::ok) // Only map ok results TODO: Maybe log warnings for skipped stems?
-                .collect();
+            // Read each audio stem (initial metadata)
+            let mut ogg_stems: Vec<(StemType, Box<dyn AudioFileReader>)> = Vec::new();
+
+            for p in ogg_stem_paths.iter() {
+                let reader = match open_audio_source(song_source, p.to_str().unwrap_or_default()) {
+                    Ok(reader) => reader,
+                    Err(err) => {
+                        song_report.add_warning(format!("Skipped audio stem \"{}\" ({})", p.display(), err));
+                        continue;
+                    }
+                };
+
+                // Multitrack audio is mixed down into single stem
+                let channel_count = reader.get_channel_count();
+                if channel_count == 0 {
+                    song_report.add_warning(format!("Skipped audio stem \"{}\" without any channels", p.display()));
+                    continue;
+                } else if channel_count > 2 {
+                    song_report.add_warning(format!("Mixed down {} channels of audio stem \"{}\"", channel_count, p.display()));
+                }
+
+                ogg_stems.push((get_stem_type(p, has_bass_stem), reader));
+            }
 
             // Gets the most common sample rate
             let common_sample_rate = *ogg_stems
                 .iter()
-                .map(|o| o.get_sample_rate())
+                .map(|(_, o)| o.get_sample_rate())
                 .fold(HashMap::<u32, usize>::new(), |mut m, sr| {
                     *m.entry(sr).or_default() += 1;
                     m
@@ -368,79 +709,105 @@ fn convert_song_audio(path: &Path, output_dir: &Path, full_song_id: &str, song_m
                 .iter()
                 .max_by_key(|(_, f)| *f)
                 .map(|(sr, _)| sr)
-                .unwrap();
-
-            // Multiple stems found, mix and re-encode
-            let mut ogg_writer = None;
+                .ok_or(PackCreateError::NoAudioFound)?;
 
             // Decode audio in parallel
             ogg_stems
                 .par_iter_mut()
-                .for_each(|reader| {
+                .for_each(|(_, reader)| {
                     reader.read_to_end();
                 });
 
-            for ogg_file in ogg_stems.iter_mut() {
-                if ogg_writer.is_none() {
-                    ogg_writer = Some(AudioWriter::new(common_sample_rate));
+            // Resample audio to properly merge
+            let mut stem_readers: Vec<(StemType, Box<dyn AudioFileReader>)> = Vec::new();
+
+            for (stem_type, reader) in ogg_stems.into_iter() {
+                match reader.get_sample_rate() {
+                    sr if sr != common_sample_rate => match reader.resample(common_sample_rate) {
+                        Some(resampled) => stem_readers.push((stem_type, Box::new(resampled))),
+                        None => song_report.add_warning(format!("Skipped {:?} audio stem that couldn't be resampled from {}hz to {}hz", stem_type, sr, common_sample_rate)),
+                    },
+                    _ => stem_readers.push((stem_type, reader)),
                 }
+            }
 
-                if let Some(writer) = &mut ogg_writer {
-                    // Decode audio
-                    // ogg_file.read_to_end();
-
-                    if ogg_file.get_sample_rate() != common_sample_rate {
-                        // Resample audio to properly merge
-                        let resampled = ogg_file.resample(common_sample_rate).unwrap();
-
-                        // Merge with existing track
-                        let data = resampled.get_samples();
-                        writer.merge_from(data);
-                    } else {
-                        // Merge with existing track
-                        let data = ogg_file.get_samples();
-                        writer.merge_from(data);
-                    }
+            let stem_samples = stem_readers
+                .iter()
+                .map(|(stem_type, reader)| (*stem_type, reader.get_samples()))
+                .collect::<Vec<(StemType, &Vec<Vec<i16>>)>>();
+
+            // Mix everything together first so all stems share same length
+            let mut mix_writer = AudioWriter::new(common_sample_rate);
+            for (_, samples) in stem_samples.iter() {
+                mix_writer.merge_from(samples);
+            }
+
+            let mut backing_writer = mix_writer.create_silent_copy();
+            let mut guitar_writer = mix_writer.create_silent_copy();
+            let mut bass_writer = mix_writer.create_silent_copy();
+
+            for (stem_type, samples) in stem_samples.iter() {
+                let writer = match stem_type {
+                    StemType::Backing => &mut backing_writer,
+                    StemType::Guitar => &mut guitar_writer,
+                    StemType::Bass => &mut bass_writer,
+                };
+
+                writer.merge_from(samples);
+            }
+
+            // Add silence to start so notes with negative offset line up
+            if audio_padding > 0 {
+                for writer in [&mut mix_writer, &mut backing_writer, &mut guitar_writer, &mut bass_writer] {
+                    writer.pad_start(audio_padding as f64);
                 }
             }
 
-            if ogg_writer.is_none() {
-                // TODO: Log error and skip song
-                return Ok(())
+            // Scale every stem by the full mix so they stay balanced against each other
+            let clipping_scale = mix_writer.get_clipping_scale();
+            for writer in [&mut mix_writer, &mut backing_writer, &mut guitar_writer, &mut bass_writer] {
+                writer.scale_samples(clipping_scale);
             }
 
-            // Encode mixed audio and write to file
-            let mut ogg_writer = ogg_writer.unwrap();
-            ogg_writer.fix_clipping();
-            ogg_writer.save_as_ogg(&gp_backing_file_path, None);
+            // Encode backing audio and write to file
+            backing_writer.save_as_ogg(&gp_backing_file_path, None)?;
 
             // "Encrypt"
             ogg_to_dpo(&gp_backing_file_path, &gp_backing_file_path)?;
 
-            // Generate preview audio
-            let preview_writer = create_preview_audio(&ogg_writer, song_meta.preview_start.unwrap_or(20_000));
-            preview_writer.save_as_ogg(&gp_preview_file_path, None);
-            ogg_preview_path = Some(&gp_preview_file_path);
+            // Generate preview audio from full mix if song doesn't have one
+            if ogg_preview_path.is_none() {
+                let preview_start = song_meta.preview_start
+                    .map(|p| p + audio_padding as u32)
+                    .unwrap_or(20_000);
+                let preview_end = song_meta.preview_end
+                    .map(|p| p + audio_padding as u32);
+                let preview_writer = create_preview_audio(&mix_writer, preview_start, preview_end);
+                preview_writer.save_as_ogg(&gp_preview_file_path, None)?;
+                ogg_to_dpo(&gp_preview_file_path, &gp_preview_file_path)?;
+            }
 
-            // Write silence for instrument "stems"
-            ogg_writer.make_silent();
-            save_instrument_stems(&ogg_writer, output_dir, full_song_id)?;
+            // Write guitar/bass stems (silent if not found)
+            save_instrument_stems(&guitar_writer, &bass_writer, output_dir, full_song_id)?;
         }
     }
 
-    if let Some(prevew_path) = ogg_preview_path {
-        // Preview exists, just copy and "encrypt"
-        ogg_to_dpo(prevew_path, &gp_preview_file_path)?;
-    } else {
-        // Generate preview from mixed audio
+    if let Some(preview_path) = ogg_preview_path {
+        // Preview exists in song, just copy and "encrypt"
+        let preview_data = song_source.read_file(preview_path.to_str().unwrap_or_default())?;
+        ogg_bytes_to_dpo(&preview_data, &gp_preview_file_path)?;
     }
 
     Ok(())
 }
 
-fn create_preview_audio(mixed_audio: &AudioWriter, preview_start: u32) -> AudioWriter {
+fn create_preview_audio(mixed_audio: &AudioWriter, preview_start: u32, preview_end: Option<u32>) -> AudioWriter {
+    // Use preview end from song.ini if valid, otherwise default to 30s
+    let preview_time = match preview_end {
+        Some(end) if end > preview_start => (end - preview_start).min(30_000) as f64,
+        _ => 30_000.0,
+    };
     let preview_start = preview_start as f64;
-    let preview_time = 30_000.0;
 
     let (start_pos, preview_len) = match mixed_audio.get_length_in_ms() {
         l if l >= (preview_start + preview_time) => (preview_start, preview_time),
@@ -451,23 +818,81 @@ fn create_preview_audio(mixed_audio: &AudioWriter, preview_start: u32) -> AudioW
     mixed_audio.create_sub_writer(start_pos, preview_len)
 }
 
-fn save_instrument_stems(silent_audio: &AudioWriter, output_dir: &Path, full_song_id: &str) -> Result<(), Box<dyn Error>> {
+fn save_instrument_stems(guitar_audio: &AudioWriter, bass_audio: &AudioWriter, output_dir: &Path, full_song_id: &str) -> Result<(), PraiseError> {
     // Create paths for guitar/bass stems
-    let audio_paths: Vec<PathBuf> = (0..4)
+    let guitar_paths: Vec<PathBuf> = (0..4)
         .map(|i| output_dir.join(format!("GPG{}_{}.dpo", full_song_id, i)))
-        .chain((0..2)
-            .map(|i| output_dir.join(format!("GPB{}_{}.dpo", full_song_id, i))))
         .collect();
 
-    // Save and "encrypt"
-    let audio_path = &audio_paths[0];
-    silent_audio.save_as_ogg(&audio_path, None);
-    ogg_to_dpo(&audio_path, &audio_path)?;
+    let bass_paths: Vec<PathBuf> = (0..2)
+        .map(|i| output_dir.join(format!("GPB{}_{}.dpo", full_song_id, i)))
+        .collect();
+
+    for (audio, audio_paths) in [(guitar_audio, guitar_paths), (bass_audio, bass_paths)] {
+        // Save and "encrypt"
+        let audio_path = &audio_paths[0];
+        audio.save_as_ogg(&audio_path, None)?;
+        ogg_to_dpo(&audio_path, &audio_path)?;
 
-    // Copy audio for other paths
-    for out_audio_path in audio_paths.iter().skip(1) {
-        copy_ogg_file(&audio_path, &out_audio_path)?;
+        // Copy audio for other paths
+        for out_audio_path in audio_paths.iter().skip(1) {
+            copy_ogg_file(&audio_path, &out_audio_path)?;
+        }
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_replacement_keeps_existing_song() {
+        let root_dir = std::env::temp_dir().join(format!("praise_mod_creator_{}", std::process::id()));
+        let songs_dir = root_dir.join("songs");
+        let song_dir = songs_dir.join("song");
+        let output_dir = root_dir.join("ep05");
+        let existing_song_dir = output_dir.join("004");
+
+        // Matched song is missing chart so conversion fails
+        create_dir_all(&song_dir).unwrap();
+        write(song_dir.join("song.ini"), "[song]\nname = Song\nartist = Artist\n").unwrap();
+
+        create_dir_all(&existing_song_dir).unwrap();
+        write(existing_song_dir.join("GPM05004.dpo"), "existing").unwrap();
+
+        let existing_meta = SongMeta {
+            name: String::from("Song"),
+            artist: String::from("Artist"),
+            ..Default::default()
+        };
+
+        let song_sources = SongSources {
+            paths: vec![song_dir.to_owned()],
+            ..Default::default()
+        };
+
+        let target = PackTarget {
+            output_dir: output_dir.to_owned(),
+            song_sources: &song_sources,
+            songs_path: songs_dir.to_str().unwrap(),
+            pack_name: "Pack",
+            pack_id: 5,
+            no_cache: true,
+            existing_songs: vec![(existing_meta, 4, SongRatings::default())],
+        };
+
+        let pack_report = write_songs_to_pack(target, &song_sources.paths).unwrap();
+
+        assert_eq!(1, pack_report.songs.len());
+        assert_eq!(4, pack_report.songs[0].song_id);
+        assert_eq!(SongStatus::Failed, pack_report.songs[0].status);
+        assert_eq!(Some(PackCreateError::NoChartFound.to_string()), pack_report.songs[0].error);
+
+        assert_eq!("existing", std::fs::read_to_string(existing_song_dir.join("GPM05004.dpo")).unwrap());
+        assert!(!output_dir.join(format!("004{}", TEMP_SONG_DIR_SUFFIX)).exists());
+
+        remove_dir_all(&root_dir).unwrap();
+    }
+}
@@ -2,6 +2,41 @@ use thiserror::Error as ThisError;
 
 #[derive(Debug, ThisError)]
 pub enum PackCreateError {
-    #[error("No ogg audio found")]
+    #[error("No supported audio found")]
     NoAudioFound,
+    #[error("No chart found in either .chart or .mid format")]
+    NoChartFound,
+    #[error("Pack id value of {id} is not valid (must be between 4-98)")]
+    InvalidPackId {
+        id: u8,
+    },
+    #[error("Found {count} songs which is over 1000 song limit")]
+    TooManySongs {
+        count: usize,
+    },
+    #[error("No free song ids left (must be between 0-999)")]
+    NoFreeSongId,
+    #[error("No master.xml found in existing pack")]
+    NoMasterXmlFound,
+    #[error("Can't create {count} packs starting from id {first_id} (ids must be between 4-98)")]
+    TooManyPacks {
+        count: usize,
+        first_id: u8,
+    },
+}
+
+#[derive(Debug, ThisError)]
+pub enum PackExtractError {
+    #[error("No master.xml found")]
+    NoMasterXmlFound,
+    #[error("No backing audio found for song {song_id}")]
+    NoBackingAudioFound {
+        song_id: String,
+    },
+    #[error("No songs found in master.xml")]
+    NoSongsFound,
+    #[error("None of the {count} songs could be extracted")]
+    NoSongsExtracted {
+        count: usize,
+    },
 }
\ No newline at end of file
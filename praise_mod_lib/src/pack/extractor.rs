@@ -0,0 +1,594 @@
+use crate::audio::*;
+use crate::chart::*;
+use crate::midi::*;
+use crate::pack::*;
+use crate::xml::*;
+use crate::PraiseError;
+use ini::Ini;
+use log::{debug, error, info, warn};
+use rayon::prelude::*;
+use std::collections::HashSet;
+use std::fs::{copy, create_dir_all};
+use std::path::Path;
+use std::time::Instant;
+
+// 480 ticks per beat @ 125 bpm = 1 tick per ms
+const EXTRACT_RESOLUTION: u16 = 480;
+const EXTRACT_MPQ: u32 = 480_000;
+const EXTRACT_BPM: u32 = 125_000; // bpm * 1000
+
+// Clone Hero default: notes within 65/192 of a beat after a different note are hopos
+const NATURAL_HOPO_THRESHOLD: u64 = (EXTRACT_RESOLUTION as u64 * 65) / 192;
+
+struct ExtractedSong<'a> {
+    full_song_id: String,
+    song: &'a XmlSong,
+    album: &'a str,
+    dir_name: String,
+}
+
+struct ExtractedNotes {
+    guitar: Vec<Vec<BeatEvent>>, // Easy -> Expert
+    bass: Vec<Vec<BeatEvent>>,
+    vocals: Vec<LyricEvent>,
+}
+
+pub fn extract_pack(ops: &PackExtractOptions) -> Result<(), PraiseError> {
+    // Start timer
+    let overall_start_time = Instant::now();
+
+    let pack_dir = Path::new(&ops.pack_path);
+    let master_path = pack_dir.join("master.xml");
+
+    if !master_path.exists() {
+        error!("No master.xml found in \"{}\"", &ops.pack_path);
+        return Err(PackExtractError::NoMasterXmlFound.into());
+    }
+
+    let (pack_name, pack_id, albums, songs) = match XmlFile::from_path(&master_path)?.track {
+        XmlTrack::Metadata { name, pack_id, albums, songs } => (name, pack_id, albums, songs),
+        _ => return Err(PackExtractError::NoMasterXmlFound.into()),
+    };
+
+    // Fallback to pack directory name (i.e. ep04)
+    let pack_id = match pack_id {
+        0 => get_pack_id_from_dir_name(pack_dir).unwrap_or_default(),
+        id => id,
+    };
+
+    if songs.is_empty() {
+        error!("No songs found in \"{}\"", master_path.display());
+        return Err(PackExtractError::NoSongsFound.into());
+    }
+
+    info!(
+        "Extracting {} songs from song pack \"{}\" with id {:03}",
+        songs.len(),
+        &pack_name,
+        pack_id
+    );
+
+    // Assign unique directory names up front
+    let mut used_names = HashSet::new();
+    let extracted_songs = songs
+        .iter()
+        .map(|song| {
+            let full_song_id = format!("{:02}{:03}", pack_id, song.song_id);
+
+            let mut dir_name = sanitize_file_name(&format!("{} - {}", &song.artist, &song.title));
+            if dir_name.is_empty() || used_names.contains(&dir_name.to_ascii_lowercase()) {
+                dir_name = format!("{} ({})", dir_name, &full_song_id)
+                    .trim()
+                    .to_string();
+            }
+            used_names.insert(dir_name.to_ascii_lowercase());
+
+            ExtractedSong {
+                full_song_id,
+                song,
+                album: albums
+                    .get(song.album_idx as usize)
+                    .map(|a| a.as_str())
+                    .unwrap_or_default(),
+                dir_name,
+            }
+        })
+        .collect::<Vec<ExtractedSong>>();
+
+    let output_dir = Path::new(&ops.output_path);
+    let song_count = extracted_songs.len();
+
+    let extracted_count = extracted_songs
+        .par_iter()
+        .map(|ex_song| {
+            let song_output_dir = output_dir.join(&ex_song.dir_name);
+
+            match extract_song(pack_dir, ex_song, &song_output_dir, ops.midi) {
+                Ok(_) => {
+                    info!(
+                        "Successfully extracted \"{} - {}\"",
+                        &ex_song.song.title,
+                        &ex_song.song.artist
+                    );
+                    true
+                },
+                Err(err) => {
+                    warn!(
+                        "Error extracting song {} ({}), skipping",
+                        &ex_song.full_song_id,
+                        err
+                    );
+                    false
+                }
+            }
+        })
+        .filter(|res| *res)
+        .count();
+
+    info!(
+        "Extracted {}/{} songs in {:.3}s",
+        extracted_count,
+        song_count,
+        overall_start_time.elapsed().as_secs_f64()
+    );
+
+    if extracted_count == 0 {
+        return Err(PackExtractError::NoSongsExtracted { count: song_count }.into());
+    }
+
+    Ok(())
+}
+
+pub(crate) fn get_pack_id_from_dir_name(pack_dir: &Path) -> Option<u8> {
+    pack_dir
+        .file_name()
+        .and_then(|n| n.to_str())
+        .and_then(|n| n.strip_prefix("ep"))
+        .and_then(|id| id.parse::<u8>().ok())
+}
+
+fn extract_song(pack_dir: &Path, ex_song: &ExtractedSong, output_dir: &Path, as_midi: bool) -> Result<(), PraiseError> {
+    let full_song_id = &ex_song.full_song_id;
+    let song_dir = pack_dir.join(format!("{:03}", ex_song.song.song_id));
+
+    let gp_backing_path = song_dir.join(format!("GPM{}.dpo", full_song_id));
+    if !gp_backing_path.exists() {
+        return Err(PackExtractError::NoBackingAudioFound {
+            song_id: full_song_id.to_owned(),
+        }.into());
+    }
+
+    if !output_dir.exists() {
+        create_dir_all(output_dir)?;
+    }
+
+    // "Decrypt" audio
+    dpo_to_ogg(&gp_backing_path, &output_dir.join("song.ogg"))?;
+
+    let optional_audio = [
+        (format!("GPP{}.dpo", full_song_id), "preview.ogg"),
+        (format!("GPG{}_0.dpo", full_song_id), "guitar.ogg"),
+        (format!("GPB{}_0.dpo", full_song_id), "bass.ogg"),
+    ];
+
+    for (gp_file_name, ch_file_name) in optional_audio.iter() {
+        let gp_path = song_dir.join(gp_file_name);
+
+        if gp_path.exists() {
+            dpo_to_ogg(&gp_path, &output_dir.join(ch_file_name))?;
+        } else {
+            debug!("\"{}\" not found, skipping", gp_path.display());
+        }
+    }
+
+    // Copy album art
+    let gp_art_path = song_dir.join(format!("GPC{}.png", full_song_id));
+    if gp_art_path.exists() {
+        copy(&gp_art_path, output_dir.join("album.png"))?;
+    }
+
+    // Convert notes
+    let notes = read_song_notes(&song_dir, full_song_id)?;
+
+    if as_midi {
+        write_notes_as_midi(&notes, ex_song, &output_dir.join("notes.mid"))?;
+    } else {
+        write_notes_as_chart(&notes, ex_song, &output_dir.join("notes.chart"))?;
+    }
+
+    write_song_ini(ex_song, &output_dir.join("song.ini"))?;
+
+    Ok(())
+}
+
+fn read_song_notes(song_dir: &Path, full_song_id: &str) -> Result<ExtractedNotes, PraiseError> {
+    let read_beats = |prefix: &str| -> Result<Vec<Vec<BeatEvent>>, PraiseError> {
+        (0..4)
+            .map(|i| song_dir.join(format!("{}{}_{}.xml", prefix, full_song_id, i)))
+            .map(|xml_path| match xml_path.exists() {
+                true => match XmlFile::from_path(&xml_path)?.track {
+                    XmlTrack::GuitarBass(beats) => Ok(beats),
+                    _ => Ok(Vec::new()),
+                },
+                _ => Ok(Vec::new()),
+            })
+            .collect()
+    };
+
+    let lyrics_path = song_dir.join(format!("GPL{}.xml", full_song_id));
+
+    Ok(ExtractedNotes {
+        guitar: read_beats("GPG")?,
+        bass: read_beats("GPB")?,
+        vocals: match lyrics_path.exists() {
+            true => match XmlFile::from_path(&lyrics_path)?.track {
+                XmlTrack::Vocals(lyrics) => lyrics,
+                _ => Vec::new(),
+            },
+            _ => Vec::new(),
+        },
+    })
+}
+
+fn sanitize_file_name(name: &str) -> String {
+    name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>()
+        .trim_end_matches(['.', ' '])
+        .trim()
+        .to_string()
+}
+
+fn write_song_ini(ex_song: &ExtractedSong, ini_path: &Path) -> Result<(), PraiseError> {
+    let mut song_ini = Ini::new();
+
+    song_ini
+        .with_section(Some("song"))
+        .set("name", &ex_song.song.title)
+        .set("artist", &ex_song.song.artist)
+        .set("album", ex_song.album)
+        .set("delay", "0");
+
+    song_ini.write_to_file(ini_path)?;
+    Ok(())
+}
+
+fn get_fret_numbers(beat: &BeatEvent) -> Vec<u8> {
+    [beat.green, beat.red, beat.yellow, beat.blue, beat.orange]
+        .iter()
+        .enumerate()
+        .filter(|(_, enabled)| **enabled)
+        .map(|(i, _)| i as u8)
+        .collect()
+}
+
+fn is_natural_hopo(beat: &BeatEvent, prev_beat: Option<&BeatEvent>) -> bool {
+    // Chords + repeated notes are never natural hopos
+    let frets = get_fret_numbers(beat);
+
+    match prev_beat {
+        Some(prev_beat) => frets.len() == 1
+            && beat.pos - prev_beat.pos <= NATURAL_HOPO_THRESHOLD
+            && frets != get_fret_numbers(prev_beat),
+        None => false,
+    }
+}
+
+// Groups consecutive matching notes into (start, end) phrases
+fn get_note_phrases<F: Fn(&BeatEvent) -> bool>(beats: &[BeatEvent], is_match: F) -> Vec<(u64, u64)> {
+    let mut phrases = Vec::new();
+    let mut current_phrase: Option<(u64, u64)> = None;
+
+    for beat in beats.iter() {
+        match (is_match(beat), &mut current_phrase) {
+            (true, Some((_, end))) => *end = beat.pos + beat.length.max(1),
+            (true, None) => current_phrase = Some((beat.pos, beat.pos + beat.length.max(1))),
+            (false, Some((start, end))) => {
+                // Don't overlap next note
+                phrases.push((*start, (*end).min(beat.pos)));
+                current_phrase = None;
+            },
+            (false, None) => continue,
+        }
+    }
+
+    if let Some(phrase) = current_phrase {
+        phrases.push(phrase);
+    }
+
+    phrases
+}
+
+fn get_chart_text(text: &str) -> String {
+    // Quotes + line breaks can't be stored in .chart text
+    text
+        .chars()
+        .filter(|c| !matches!(c, '"' | '\r' | '\n'))
+        .collect()
+}
+
+fn write_notes_as_chart(notes: &ExtractedNotes, ex_song: &ExtractedSong, chart_path: &Path) -> Result<(), PraiseError> {
+    let song_values = [
+        ("Name", get_chart_text(&ex_song.song.title)),
+        ("Artist", get_chart_text(&ex_song.song.artist)),
+        ("Album", get_chart_text(ex_song.album)),
+        ("MusicStream", String::from("song.ogg")),
+    ];
+
+    let create_sync_event = |value| SyncEvent {
+        pos: 0,
+        pos_realtime: 0.0,
+        value,
+    };
+
+    let create_text_event = |pos, value| TextEvent {
+        pos,
+        pos_realtime: 0.0,
+        value,
+    };
+
+    let mut text_events = Vec::new();
+    for lyric in notes.vocals.iter() {
+        text_events.push(create_text_event(lyric.pos, TextEventType::PhraseStart));
+        text_events.push(create_text_event(lyric.pos, TextEventType::Lyric(get_chart_text(&lyric.text))));
+        text_events.push(create_text_event(lyric.pos + lyric.length, TextEventType::PhraseEnd));
+    }
+
+    let difficulty_names = ["Easy", "Medium", "Hard", "Expert"];
+    let instruments = [("Single", &notes.guitar), ("DoubleBass", &notes.bass)];
+
+    let mut guitar_tracks = Vec::new();
+
+    for (instrument_name, difficulties) in instruments.iter() {
+        for (diff_name, beats) in difficulty_names.iter().zip(difficulties.iter()) {
+            if beats.is_empty() {
+                continue;
+            }
+
+            let create_guitar_event = |pos, length, value| GuitarEvent {
+                pos,
+                pos_realtime: 0.0,
+                length,
+                length_realtime: 0.0,
+                value,
+            };
+
+            let mut events = Vec::new();
+            let mut prev_beat = None;
+
+            for beat in beats.iter() {
+                for fret in get_fret_numbers(beat) {
+                    events.push(create_guitar_event(beat.pos, beat.length, GuitarEventType::Note(fret as u32)));
+                }
+
+                // Quick notes are hopos, forced flag flips natural hopo state
+                if beat.tap != is_natural_hopo(beat, prev_beat) {
+                    events.push(create_guitar_event(beat.pos, 0, GuitarEventType::Forced));
+                }
+
+                prev_beat = Some(beat);
+            }
+
+            for (start, end) in get_note_phrases(beats, |b| b.star_power) {
+                events.push(create_guitar_event(start, end - start, GuitarEventType::Starpower));
+            }
+
+            guitar_tracks.push(GuitarTrack {
+                name: format!("{}{}", diff_name, instrument_name),
+                events,
+            });
+        }
+    }
+
+    let chart = SongChart {
+        resolution: EXTRACT_RESOLUTION,
+        offset: 0.0,
+        song_values: song_values
+            .iter()
+            .map(|(key, value)| (key.to_string(), format!("\"{}\"", value)))
+            .collect(),
+        sync_track: SyncTrack {
+            events: vec![
+                create_sync_event(SyncEventType::TimeSignature(4, None)),
+                create_sync_event(SyncEventType::Beat(EXTRACT_BPM)),
+            ],
+        },
+        event_track: EventTrack {
+            events: text_events,
+        },
+        guitar_tracks,
+    };
+
+    chart.write_to_path(chart_path)
+}
+
+fn write_notes_as_midi(notes: &ExtractedNotes, ex_song: &ExtractedSong, midi_path: &Path) -> Result<(), PraiseError> {
+    let instruments = [("PART GUITAR", &notes.guitar), ("PART BASS", &notes.bass)];
+    let note_offsets = [60u8, 72, 84, 96];
+
+    let mut tracks = Vec::new();
+
+    for (track_name, difficulties) in instruments.iter() {
+        if difficulties.iter().all(|beats| beats.is_empty()) {
+            continue;
+        }
+
+        let mut track = create_midi_track(track_name);
+
+        for (beats, offset) in difficulties.iter().zip(note_offsets.iter()) {
+            for beat in beats.iter() {
+                for fret in get_fret_numbers(beat) {
+                    track.notes.push(create_midi_note(beat.pos, beat.length.max(1), offset + fret));
+                }
+            }
+
+            // Quick notes are played as hopos, use forced hopo phrases
+            for (start, end) in get_note_phrases(beats, |b| b.tap) {
+                track.notes.push(create_midi_note(start, end - start, offset + 5));
+            }
+        }
+
+        // Star power is shared across difficulties, use highest charted difficulty
+        if let Some(beats) = difficulties.iter().rev().find(|beats| !beats.is_empty()) {
+            for (start, end) in get_note_phrases(beats, |b| b.star_power) {
+                track.notes.push(create_midi_note(start, end - start, 116));
+            }
+        }
+
+        tracks.push(track);
+    }
+
+    if !notes.vocals.is_empty() {
+        let mut track = create_midi_track("PART VOCALS");
+
+        for lyric in notes.vocals.iter() {
+            track.notes.push(create_midi_note(lyric.pos, lyric.length.max(1), 105));
+            track.texts.push(MidiText {
+                pos: lyric.pos,
+                text: MidiTextType::Lyric(lyric.text.to_owned()),
+            });
+        }
+
+        tracks.push(track);
+    }
+
+    let midi = MidiFile {
+        format: 1,
+        ticks_per_quarter: EXTRACT_RESOLUTION,
+        conductor_track: Some(create_midi_track(&ex_song.song.title)),
+        tracks,
+        tempo: vec![MidiTempo {
+            pos: 0,
+            pos_realtime: 0.0,
+            mpq: EXTRACT_MPQ,
+            bpm: 60_000_000.0 / EXTRACT_MPQ as f64,
+        }],
+        time_signatures: vec![MidiTimeSignature {
+            pos: 0,
+            numerator: 4,
+            denominator: 4,
+            clocks_per_click: 24,
+            thirty_seconds_per_quarter: 8,
+        }],
+        key_signatures: Vec::new(),
+    };
+
+    midi.write_to_path(midi_path)?;
+    Ok(())
+}
+
+fn create_midi_track(name: &str) -> MidiTrack {
+    MidiTrack {
+        name: Some(name.to_string()),
+        ..Default::default()
+    }
+}
+
+fn create_midi_note(pos: u64, length: u64, pitch: u8) -> MidiNote {
+    MidiNote {
+        pos,
+        pos_realtime: 0.0,
+        length,
+        length_realtime: 0.0,
+        pitch,
+        channel: 0,
+        velocity: 100,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn beat(pos: u64, frets: &[u8], tap: bool) -> BeatEvent {
+        let mut beat = BeatEvent::default(pos, 0);
+        beat.green = frets.contains(&0);
+        beat.red = frets.contains(&1);
+        beat.yellow = frets.contains(&2);
+        beat.blue = frets.contains(&3);
+        beat.orange = frets.contains(&4);
+        beat.tap = tap;
+        beat
+    }
+
+    fn get_test_notes() -> ExtractedNotes {
+        let expert_beats = vec![
+            beat(0, &[0], false),
+            beat(100, &[1], false), // Natural hopo, forced to strum
+            beat(200, &[2], true), // Natural hopo
+            beat(1000, &[3], true), // Too far apart, forced to hopo
+            beat(1100, &[3, 4], false), // Chord
+        ];
+
+        ExtractedNotes {
+            guitar: vec![Vec::new(), Vec::new(), Vec::new(), expert_beats],
+            bass: vec![Vec::new(); 4],
+            vocals: Vec::new(),
+        }
+    }
+
+    fn extract_notes(notes_path: &Path, as_midi: bool) {
+        let song = XmlSong {
+            song_id: 0,
+            artist: String::from("Artist"),
+            title: String::from("Song"),
+            album_idx: 0,
+            ratings: SongRatings::default(),
+        };
+
+        let ex_song = ExtractedSong {
+            full_song_id: String::from("05000"),
+            song: &song,
+            album: "Album",
+            dir_name: String::from("Artist - Song"),
+        };
+
+        match as_midi {
+            true => write_notes_as_midi(&get_test_notes(), &ex_song, notes_path).unwrap(),
+            false => write_notes_as_chart(&get_test_notes(), &ex_song, notes_path).unwrap(),
+        }
+    }
+
+    #[test]
+    fn extract_quick_notes_as_midi_forced_hopos() {
+        let midi_path = std::env::temp_dir().join(format!("praise_mod_extract_{}.mid", std::process::id()));
+        extract_notes(&midi_path, true);
+
+        let midi = MidiFile::from_path(&midi_path).unwrap();
+        std::fs::remove_file(&midi_path).unwrap();
+
+        // Re-importing gives back same quick notes
+        let beats = match XmlFile::from_midi(&midi, XmlTrackType::Guitar, Some(XmlTrackDifficulty::Expert)).track {
+            XmlTrack::GuitarBass(beats) => beats,
+            _ => Vec::new(),
+        };
+
+        assert_eq!(
+            vec![(0, false), (100, false), (200, true), (1000, true), (1100, false)],
+            beats.iter().map(|b| (b.pos, b.tap)).collect::<Vec<_>>()
+        );
+        assert!(midi.tracks[0].sysex.is_empty());
+    }
+
+    #[test]
+    fn extract_quick_notes_as_chart_forced_flags() {
+        let chart_path = std::env::temp_dir().join(format!("praise_mod_extract_{}.chart", std::process::id()));
+        extract_notes(&chart_path, false);
+
+        let chart = SongChart::from_path(&chart_path).unwrap();
+        std::fs::remove_file(&chart_path).unwrap();
+
+        // Forced flag is only added where natural hopo state differs
+        let events = &chart.guitar_tracks[0].events;
+        assert_eq!(
+            vec![100, 1000],
+            events.iter().filter(|e| e.value == GuitarEventType::Forced).map(|e| e.pos).collect::<Vec<_>>()
+        );
+        assert!(!events.iter().any(|e| e.value == GuitarEventType::Tap));
+    }
+}
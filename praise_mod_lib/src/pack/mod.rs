@@ -1,9 +1,23 @@
+mod appender;
+mod cache;
 mod chart_file;
 mod creator;
 mod errors;
+mod extractor;
 mod options;
+mod ordering;
+mod report;
+mod split;
+mod validator;
 
+pub use self::appender::*;
+pub use self::cache::*;
 pub use self::chart_file::*;
 pub use self::creator::*;
 pub use self::errors::*;
+pub use self::extractor::*;
 pub use self::options::*;
+pub use self::ordering::*;
+pub use self::report::*;
+pub use self::split::*;
+pub use self::validator::*;
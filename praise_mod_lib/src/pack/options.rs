@@ -1,7 +1,26 @@
+use crate::pack::SongOrder;
+
 #[derive(Debug)]
 pub struct PackOptions {
     pub songs_path: String,
     pub output_path: String,
     pub name: Option<String>,
     pub id: u8,
-}
\ No newline at end of file
+    pub no_cache: bool,
+    pub order: SongOrder,
+}
+
+#[derive(Debug)]
+pub struct PackExtractOptions {
+    pub pack_path: String,
+    pub output_path: String,
+    pub midi: bool,
+}
+
+#[derive(Debug)]
+pub struct PackAddOptions {
+    pub songs_path: String,
+    pub pack_path: String,
+    pub no_cache: bool,
+    pub order: SongOrder,
+}
@@ -0,0 +1,294 @@
+use crate::PraiseError;
+use crate::shared::write_json_file;
+use crate::pack::PackCreateError;
+use crate::song::SongMeta;
+use crate::vfs::SongSources;
+use crate::xml::SongRatings;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{read, read_to_string};
+use std::path::{Path, PathBuf};
+
+pub const ID_MAP_FILE_NAME: &str = "id_map.json";
+pub const MAX_SONG_ID: u16 = 999;
+pub const MAX_SONGS_PER_PACK: usize = 999; // Song count must stay under 1000
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum SongOrder {
+    #[default]
+    Path,
+    Title,
+    Artist,
+    Album, // Album then track number
+    Manifest(PathBuf), // Text file with one song path per line
+}
+
+// Keeps song ids stable across rebuilds of same pack
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct SongIdMap {
+    pub songs: BTreeMap<String, u16>, // Song path (relative to songs dir) -> id
+}
+
+impl SongIdMap {
+    pub fn from_path<T: AsRef<Path>>(path: T) -> Result<SongIdMap, PraiseError> {
+        let path = path.as_ref();
+
+        if !path.exists() {
+            return Ok(SongIdMap::default());
+        }
+
+        let data = read(path)?;
+        let id_map = serde_json::from_slice(&data)
+            .map_err(std::io::Error::from)?;
+
+        Ok(id_map)
+    }
+
+    pub fn write_to_file<T: AsRef<Path>>(&self, path: T) -> Result<(), PraiseError> {
+        write_json_file(self, path)
+    }
+
+    pub fn match_existing_songs(&mut self, song_keys: &[String], song_metas: &[Option<SongMeta>], existing_songs: &[(SongMeta, u16, SongRatings)]) {
+        // Fallback to title + artist for existing songs missing from id map
+        let mut mapped_ids = self.songs
+            .values()
+            .copied()
+            .collect::<HashSet<u16>>();
+
+        for (key, song_meta) in song_keys.iter().zip(song_metas.iter()) {
+            let song_meta = match song_meta {
+                Some(meta) if !self.songs.contains_key(key) => meta,
+                _ => continue,
+            };
+
+            let existing_id = existing_songs
+                .iter()
+                .find(|(meta, id, _)| !mapped_ids.contains(id)
+                    && meta.name.eq_ignore_ascii_case(&song_meta.name)
+                    && meta.artist.eq_ignore_ascii_case(&song_meta.artist))
+                .map(|(_, id, _)| *id);
+
+            if let Some(id) = existing_id {
+                debug!("Matched \"{}\" to existing song with id {:03}", key, id);
+
+                mapped_ids.insert(id);
+                self.songs.insert(key.to_owned(), id);
+            }
+        }
+    }
+
+    pub fn assign_ids(&mut self, song_keys: &[String], reserved_ids: &[u16]) -> Result<Vec<u16>, PraiseError> {
+        // Ids of removed songs stay reserved so they aren't given to different songs
+        // Reserved ids are only re-used by the song already mapped to them (replaced in place)
+        let mut used_ids = self.songs
+            .values()
+            .chain(reserved_ids.iter())
+            .copied()
+            .collect::<HashSet<u16>>();
+        let mut next_id = 0;
+
+        song_keys
+            .iter()
+            .map(|key| {
+                if let Some(id) = self.songs.get(key) {
+                    return Ok(*id);
+                }
+
+                while used_ids.contains(&next_id) {
+                    next_id += 1;
+                }
+
+                if next_id > MAX_SONG_ID {
+                    return Err(PackCreateError::NoFreeSongId.into());
+                }
+
+                used_ids.insert(next_id);
+                self.songs.insert(key.to_owned(), next_id);
+                Ok(next_id)
+            })
+            .collect()
+    }
+}
+
+pub fn get_song_key<T: AsRef<Path>, S: AsRef<Path>>(song_path: T, songs_path: S) -> String {
+    let song_path = song_path.as_ref();
+
+    song_path
+        .strip_prefix(songs_path.as_ref())
+        .unwrap_or(song_path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+pub fn order_song_paths(song_sources: &SongSources, songs_path: &str, order: &SongOrder) -> Result<Vec<PathBuf>, PraiseError> {
+    Ok(order_songs(song_sources, songs_path, order)?
+        .into_iter()
+        .map(|(p, _)| p)
+        .collect())
+}
+
+pub fn order_songs(song_sources: &SongSources, songs_path: &str, order: &SongOrder) -> Result<Vec<(PathBuf, Option<SongMeta>)>, PraiseError> {
+    // Song metadata is returned if read for sorting
+    // Path order is used as tiebreaker for others
+    let mut song_paths = song_sources.paths.to_owned();
+    song_paths.sort_by_key(|p| get_song_key(p, songs_path).to_lowercase());
+
+    match order {
+        SongOrder::Path => Ok(song_paths
+            .into_iter()
+            .map(|p| (p, None))
+            .collect()),
+        SongOrder::Title | SongOrder::Artist | SongOrder::Album => {
+            let mut songs = song_paths
+                .into_iter()
+                .map(|p| {
+                    let song_meta = read_song_meta(song_sources, &p);
+                    (song_meta.as_ref().map(|meta| get_sort_names(meta, order)), p, song_meta)
+                })
+                .collect::<Vec<_>>();
+
+            // Songs with unreadable metadata go last
+            songs.sort_by(|(a, _, _), (b, _, _)| match (a, b) {
+                (Some(a), Some(b)) => a.cmp(b),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => std::cmp::Ordering::Equal,
+            });
+
+            Ok(songs
+                .into_iter()
+                .map(|(_, p, meta)| (p, meta))
+                .collect())
+        },
+        SongOrder::Manifest(manifest_path) => Ok(order_song_paths_by_manifest(song_paths, songs_path, manifest_path)?
+            .into_iter()
+            .map(|p| (p, None))
+            .collect()),
+    }
+}
+
+pub(crate) fn read_song_meta(song_sources: &SongSources, song_path: &Path) -> Option<SongMeta> {
+    let song_meta = song_sources
+        .open(song_path)
+        .and_then(|source| SongMeta::from_source(source.as_ref()));
+
+    match song_meta {
+        Ok(meta) => Some(meta),
+        Err(err) => {
+            debug!("Can't read metadata for \"{}\" ({})", song_path.display(), err);
+            None
+        }
+    }
+}
+
+fn get_sort_names(meta: &SongMeta, order: &SongOrder) -> (String, String, String) {
+    let title = meta.name.to_lowercase();
+    let artist = meta.artist.to_lowercase();
+    let album = meta.album.to_lowercase();
+
+    match order {
+        SongOrder::Artist => (artist, title, album),
+        SongOrder::Album => {
+            // Songs without track number go last in album
+            let track = format!("{:010}", meta.album_track.unwrap_or(u32::MAX));
+            (album, track, title)
+        },
+        _ => (title, artist, album),
+    }
+}
+
+fn order_song_paths_by_manifest(song_paths: Vec<PathBuf>, songs_path: &str, manifest_path: &Path) -> Result<Vec<PathBuf>, PraiseError> {
+    let manifest = read_to_string(manifest_path)?;
+
+    let mut remaining_paths = song_paths
+        .into_iter()
+        .map(|p| (get_song_key(&p, songs_path).to_lowercase(), p))
+        .collect::<HashMap<_, _>>();
+
+    let mut ordered_paths = Vec::new();
+
+    for line in manifest.lines().map(|l| l.trim()) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let key = get_song_key(line, songs_path)
+            .trim_end_matches('/')
+            .to_lowercase();
+
+        match remaining_paths.remove(&key) {
+            Some(path) => ordered_paths.push(path),
+            None => warn!("Song \"{}\" from manifest not found", line),
+        }
+    }
+
+    // Add songs missing from manifest at end
+    let mut unlisted_paths = remaining_paths
+        .into_iter()
+        .collect::<Vec<_>>();
+    unlisted_paths.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    for (_, path) in unlisted_paths {
+        warn!("Song \"{}\" not listed in manifest, adding at end", path.display());
+        ordered_paths.push(path);
+    }
+
+    Ok(ordered_paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(names: &[&str]) -> Vec<String> {
+        names
+            .iter()
+            .map(|n| n.to_string())
+            .collect()
+    }
+
+    fn song(name: &str, artist: &str, id: u16) -> (SongMeta, u16, SongRatings) {
+        let song_meta = SongMeta {
+            name: name.to_string(),
+            artist: artist.to_string(),
+            ..Default::default()
+        };
+
+        (song_meta, id, SongRatings::default())
+    }
+
+    #[test]
+    fn append_twice_reuses_ids() {
+        let mut id_map = SongIdMap::default();
+        let existing_songs = vec![song("Existing", "Artist", 0)];
+
+        let first_ids = id_map.assign_ids(&keys(&["a", "b"]), &[0]).unwrap();
+        assert_eq!(vec![1, 2], first_ids);
+
+        // Second run sees appended songs in master.xml
+        let existing_ids = [0, 1, 2];
+        let second_ids = id_map.assign_ids(&keys(&["a", "b", "c"]), &existing_ids).unwrap();
+        assert_eq!(vec![1, 2, 3], second_ids);
+
+        id_map.match_existing_songs(&keys(&["c"]), &[None], &existing_songs);
+        assert_eq!(Some(&3), id_map.songs.get("c"));
+    }
+
+    #[test]
+    fn match_existing_songs_by_title_and_artist() {
+        let mut id_map = SongIdMap::default();
+        let existing_songs = vec![song("Song", "Artist", 4), song("Other", "Artist", 7)];
+
+        let song_keys = keys(&["song", "new"]);
+        let song_metas = vec![
+            Some(song("song", "ARTIST", 0).0),
+            Some(song("New", "Artist", 0).0),
+        ];
+
+        id_map.match_existing_songs(&song_keys, &song_metas, &existing_songs);
+        let song_ids = id_map.assign_ids(&song_keys, &[4, 7]).unwrap();
+
+        assert_eq!(vec![4, 0], song_ids);
+    }
+}
\ No newline at end of file
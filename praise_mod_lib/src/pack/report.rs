@@ -0,0 +1,115 @@
+use crate::PraiseError;
+use crate::shared::write_json_file;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SongStatus {
+    Converted,
+    Cached,
+    Failed,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SongTimings {
+    // Milliseconds
+    pub metadata: u64,
+    pub cache: u64,
+    pub chart: u64,
+    pub art: u64,
+    pub audio: u64,
+    pub total: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SongReport {
+    pub source_path: PathBuf,
+    pub song_id: u16,
+    pub status: SongStatus,
+    pub error: Option<String>,
+    pub warnings: Vec<String>,
+    pub timings: SongTimings,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PackReport {
+    pub pack_name: String,
+    pub pack_id: u8,
+    pub output_path: PathBuf,
+    pub songs: Vec<SongReport>,
+    pub total_time: u64, // Milliseconds
+}
+
+// Summary of packs created from single library
+#[derive(Debug, Default, Serialize)]
+pub struct PackSetReport {
+    pub packs: Vec<PackReport>,
+    pub total_time: u64, // Milliseconds
+}
+
+impl SongReport {
+    pub fn new(source_path: &Path, song_id: u16) -> SongReport {
+        SongReport {
+            source_path: source_path.to_owned(),
+            song_id,
+            status: SongStatus::Converted,
+            error: None,
+            warnings: Vec::new(),
+            timings: SongTimings::default(),
+        }
+    }
+
+    pub fn add_warning<T: Into<String>>(&mut self, warning: T) {
+        self.warnings.push(warning.into());
+    }
+}
+
+impl PackReport {
+    pub fn new(pack_name: &str, pack_id: u8, output_path: &Path) -> PackReport {
+        PackReport {
+            pack_name: pack_name.to_owned(),
+            pack_id,
+            output_path: output_path.to_owned(),
+            songs: Vec::new(),
+            total_time: 0,
+        }
+    }
+
+    pub fn converted_count(&self) -> usize {
+        // Includes songs re-used from cache
+        self.songs
+            .iter()
+            .filter(|s| s.status != SongStatus::Failed)
+            .count()
+    }
+
+    pub fn cached_count(&self) -> usize {
+        self.songs
+            .iter()
+            .filter(|s| s.status == SongStatus::Cached)
+            .count()
+    }
+
+    pub fn failed_count(&self) -> usize {
+        self.songs
+            .iter()
+            .filter(|s| s.status == SongStatus::Failed)
+            .count()
+    }
+
+    pub fn write_to_file<T: AsRef<Path>>(&self, path: T) -> Result<(), PraiseError> {
+        write_json_file(self, path)
+    }
+}
+
+impl PackSetReport {
+    pub fn write_to_file<T: AsRef<Path>>(&self, path: T) -> Result<(), PraiseError> {
+        write_json_file(self, path)
+    }
+}
+
+pub(crate) fn duration_to_ms(duration: Duration) -> u64 {
+    duration.as_millis() as u64
+}
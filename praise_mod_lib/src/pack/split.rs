@@ -0,0 +1,301 @@
+use crate::PraiseError;
+use crate::pack::*;
+use crate::shared::*;
+use crate::song::SongMeta;
+use crate::vfs::SongSources;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{create_dir_all, read};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+pub const PACK_MAP_FILE_NAME: &str = "pack_map.json";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PackSplit {
+    Count(usize), // Max songs per pack
+    ArtistInitial,
+    Folder, // Top-level folder in songs directory
+}
+
+// Keeps songs in same pack across rebuilds of split packs
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct SongPackMap {
+    pub split: String,
+    pub first_id: u8,
+    pub packs: BTreeMap<u8, String>, // Pack id -> group label
+    pub songs: BTreeMap<String, u8>, // Song path (relative to songs dir) -> pack id
+}
+
+impl SongPackMap {
+    pub fn from_path<T: AsRef<Path>>(path: T) -> Result<SongPackMap, PraiseError> {
+        let path = path.as_ref();
+
+        if !path.exists() {
+            return Ok(SongPackMap::default());
+        }
+
+        let data = read(path)?;
+        let pack_map = serde_json::from_slice(&data)
+            .map_err(std::io::Error::from)?;
+
+        Ok(pack_map)
+    }
+
+    pub fn write_to_file<T: AsRef<Path>>(&self, path: T) -> Result<(), PraiseError> {
+        write_json_file(self, path)
+    }
+}
+
+pub fn create_split_packs(ops: &PackOptions, split: PackSplit) -> Result<PackSetReport, PraiseError> {
+    // Start timer
+    let overall_start_time = Instant::now();
+
+    let base_name = match &ops.name {
+        Some(n) => n,
+        None => DEFAULT_PACK_NAME,
+    };
+
+    let mut set_report = PackSetReport::default();
+
+    // Find CH song directories + song archives
+    let song_sources = find_song_sources(&ops.songs_path)?;
+    let songs = order_songs(&song_sources, &ops.songs_path, &ops.order)?;
+
+    if songs.is_empty() {
+        error!("No songs found in \"{}\"", &ops.songs_path);
+        return Ok(set_report);
+    }
+
+    // Re-use pack assignments from previous builds
+    let output_dir = Path::new(&ops.output_path);
+    if ops.id < 4 || ops.id > 98 {
+        return Err(PackCreateError::InvalidPackId { id: ops.id }.into());
+    }
+
+    let pack_map_path = output_dir.join(PACK_MAP_FILE_NAME);
+    let mut pack_map = SongPackMap::from_path(&pack_map_path)?;
+
+    let pack_groups = get_pack_groups(&song_sources, songs, &ops.songs_path, split, ops.id, &mut pack_map);
+
+    // Packs get ids starting from given id
+    let last_id = pack_groups
+        .iter()
+        .map(|(id, _, _)| *id as usize)
+        .max()
+        .unwrap_or(ops.id as usize);
+
+    if last_id > 98 {
+        return Err(PackCreateError::TooManyPacks { count: pack_groups.len(), first_id: ops.id }.into());
+    }
+
+    create_dir_all(output_dir)?;
+    pack_map.write_to_file(&pack_map_path)?;
+
+    info!("Splitting {} songs into {} packs", pack_groups.iter().map(|(_, _, p)| p.len()).sum::<usize>(), pack_groups.len());
+
+    for (pack_id, label, group_paths) in pack_groups.iter() {
+        let pack_id = *pack_id;
+        let pack_name = match label.is_empty() {
+            true => base_name.to_owned(),
+            false => format!("{} ({})", base_name, label),
+        };
+
+        let pack_report = create_pack_from_paths(ops, &song_sources, group_paths, &pack_name, pack_id)?;
+        set_report.packs.push(pack_report);
+    }
+
+    set_report.total_time = duration_to_ms(overall_start_time.elapsed());
+
+    // Print summary
+    for pack_report in set_report.packs.iter() {
+        info!(
+            "ep{:02} \"{}\": {} songs converted, {} failed",
+            pack_report.pack_id,
+            &pack_report.pack_name,
+            pack_report.converted_count(),
+            pack_report.failed_count()
+        );
+    }
+
+    Ok(set_report)
+}
+
+fn get_pack_groups(song_sources: &SongSources, songs: Vec<(PathBuf, Option<SongMeta>)>, songs_path: &str, split: PackSplit, first_id: u8, pack_map: &mut SongPackMap) -> Vec<(u8, String, Vec<PathBuf>)> {
+    let max_songs = match split {
+        PackSplit::Count(count) => count.clamp(1, MAX_SONGS_PER_PACK),
+        _ => MAX_SONGS_PER_PACK,
+    };
+
+    // Previous assignments are dropped if split changed
+    let split_name = format!("{:?}", split);
+    if pack_map.split != split_name || pack_map.first_id != first_id {
+        *pack_map = SongPackMap {
+            split: split_name,
+            first_id,
+            ..Default::default()
+        };
+    }
+
+    let groups = match split {
+        PackSplit::Count(_) => group_songs(songs, |_, _| String::new()),
+        PackSplit::ArtistInitial => group_songs(songs, |p, meta| get_artist_initial(song_sources, p, meta)),
+        PackSplit::Folder => group_songs(songs, |p, _| get_top_level_folder(p, songs_path)),
+    };
+
+    // Songs from previous builds stay in same pack
+    let mut packs = BTreeMap::<u8, Vec<PathBuf>>::new();
+    let mut new_songs = Vec::new();
+
+    for (label, paths) in groups {
+        for path in paths {
+            let key = get_song_key(&path, songs_path);
+
+            match pack_map.songs.get(&key) {
+                Some(id) if pack_map.packs.contains_key(id) => packs.entry(*id).or_default().push(path),
+                _ => new_songs.push((label.to_owned(), key, path)),
+            }
+        }
+    }
+
+    // New songs fill up last pack with same label, then start new pack
+    for (label, key, path) in new_songs {
+        let last_pack_id = pack_map.packs
+            .iter()
+            .filter(|(_, l)| **l == label)
+            .map(|(id, _)| *id)
+            .last()
+            .filter(|id| packs.get(id).map(|p| p.len()).unwrap_or_default() < max_songs);
+
+        let pack_id = match last_pack_id {
+            Some(id) => id,
+            None => {
+                let id = match pack_map.packs.keys().last() {
+                    Some(id) => id.saturating_add(1),
+                    None => first_id,
+                };
+
+                pack_map.packs.insert(id, label);
+                id
+            }
+        };
+
+        pack_map.songs.insert(key, pack_id);
+        packs.entry(pack_id).or_default().push(path);
+    }
+
+    // Number packs sharing same label
+    packs
+        .into_iter()
+        .map(|(id, paths)| {
+            let label = &pack_map.packs[&id];
+            let same_label_ids = pack_map.packs
+                .iter()
+                .filter(|(_, l)| *l == label)
+                .map(|(id, _)| *id)
+                .collect::<Vec<_>>();
+
+            let pack_label = match (label.is_empty(), same_label_ids.len()) {
+                (_, 1) => label.to_owned(),
+                (true, _) => format!("{}", same_label_ids.iter().position(|i| *i == id).unwrap_or_default() + 1),
+                (false, _) => format!("{} {}", label, same_label_ids.iter().position(|i| *i == id).unwrap_or_default() + 1),
+            };
+
+            (id, pack_label, paths)
+        })
+        .collect()
+}
+
+fn group_songs<F: Fn(&Path, Option<&SongMeta>) -> String>(songs: Vec<(PathBuf, Option<SongMeta>)>, get_label: F) -> Vec<(String, Vec<PathBuf>)> {
+    // Song order is kept within each group
+    let mut groups = BTreeMap::<String, Vec<PathBuf>>::new();
+
+    for (path, song_meta) in songs {
+        groups
+            .entry(get_label(&path, song_meta.as_ref()))
+            .or_default()
+            .push(path);
+    }
+
+    groups
+        .into_iter()
+        .collect()
+}
+
+fn get_artist_initial(song_sources: &SongSources, song_path: &Path, song_meta: Option<&SongMeta>) -> String {
+    // Metadata is only read here if not already read for sorting
+    let artist = match song_meta {
+        Some(meta) => meta.artist.to_owned(),
+        None => read_song_meta(song_sources, song_path)
+            .map(|meta| meta.artist)
+            .unwrap_or_default(),
+    };
+
+    // Ignore leading "The"
+    let artist = artist.trim();
+    let artist = match artist.get(..4) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("the ") => &artist[4..],
+        _ => artist,
+    };
+
+    match artist.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() => c.to_ascii_uppercase().to_string(),
+        _ => String::from("#"),
+    }
+}
+
+fn get_top_level_folder(song_path: &Path, songs_path: &str) -> String {
+    let song_key = get_song_key(song_path, songs_path);
+
+    // Songs directly in songs directory don't have folder
+    match song_key.split_once('/') {
+        Some((folder, _)) => folder.to_owned(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn songs(names: &[&str]) -> Vec<(PathBuf, Option<SongMeta>)> {
+        names
+            .iter()
+            .map(|n| (Path::new("songs").join(n), None))
+            .collect()
+    }
+
+    fn group_names(pack_groups: &[(u8, String, Vec<PathBuf>)]) -> Vec<(u8, String, Vec<String>)> {
+        pack_groups
+            .iter()
+            .map(|(id, label, paths)| (*id, label.to_owned(), paths.iter().map(|p| get_song_key(p, "songs")).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn count_split_keeps_song_packs() {
+        let mut pack_map = SongPackMap::default();
+        let split = PackSplit::Count(2);
+
+        let pack_groups = get_pack_groups(&SongSources::default(), songs(&["b", "c", "d"]), "songs", split, 10, &mut pack_map);
+        assert_eq!(
+            vec![
+                (10, String::from("1"), vec![String::from("b"), String::from("c")]),
+                (11, String::from("2"), vec![String::from("d")]),
+            ],
+            group_names(&pack_groups)
+        );
+
+        // New songs only fill up last pack, even if ordered first
+        let pack_groups = get_pack_groups(&SongSources::default(), songs(&["a", "b", "c", "d", "e", "f"]), "songs", split, 10, &mut pack_map);
+        assert_eq!(
+            vec![
+                (10, String::from("1"), vec![String::from("b"), String::from("c")]),
+                (11, String::from("2"), vec![String::from("d"), String::from("a")]),
+                (12, String::from("3"), vec![String::from("e"), String::from("f")]),
+            ],
+            group_names(&pack_groups)
+        );
+    }
+}
\ No newline at end of file
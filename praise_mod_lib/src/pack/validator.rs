@@ -0,0 +1,293 @@
+use crate::audio::get_dpo_length_in_ms;
+use crate::pack::*;
+use crate::shared::*;
+use crate::xml::*;
+use crate::PraiseError;
+use log::{error, info};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs::{read, read_dir};
+use std::path::Path;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueSeverity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ValidationIssue {
+    pub severity: IssueSeverity,
+    pub song_id: Option<u16>,
+    pub file_name: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct PackValidationReport {
+    pub pack_name: String,
+    pub pack_id: u8,
+    pub song_count: usize,
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl PackValidationReport {
+    pub fn error_count(&self) -> usize {
+        self.issues
+            .iter()
+            .filter(|i| i.severity == IssueSeverity::Error)
+            .count()
+    }
+
+    pub fn warning_count(&self) -> usize {
+        self.issues
+            .iter()
+            .filter(|i| i.severity == IssueSeverity::Warning)
+            .count()
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.error_count() == 0
+    }
+
+    pub fn write_to_file<T: AsRef<Path>>(&self, path: T) -> Result<(), PraiseError> {
+        write_json_file(self, path)
+    }
+}
+
+// Collects issues for single song
+struct SongValidator {
+    song_id: u16,
+    issues: Vec<ValidationIssue>,
+}
+
+impl SongValidator {
+    fn add_issue(&mut self, severity: IssueSeverity, file_name: Option<&str>, message: String) {
+        self.issues.push(ValidationIssue {
+            severity,
+            song_id: Some(self.song_id),
+            file_name: file_name.map(|f| f.to_owned()),
+            message,
+        });
+    }
+
+    fn add_error(&mut self, file_name: &str, message: String) {
+        self.add_issue(IssueSeverity::Error, Some(file_name), message);
+    }
+}
+
+pub fn validate_pack<T: AsRef<Path>>(pack_path: T) -> Result<PackValidationReport, PraiseError> {
+    let pack_dir = pack_path.as_ref();
+    let master_path = pack_dir.join("master.xml");
+
+    if !master_path.exists() {
+        error!("No master.xml found in \"{}\"", pack_dir.display());
+        return Err(PackCreateError::NoMasterXmlFound.into());
+    }
+
+    let (pack_name, pack_id, songs) = match XmlFile::from_path(&master_path)?.track {
+        XmlTrack::Metadata { name, pack_id, songs, .. } => (name, pack_id, songs),
+        _ => return Err(PackCreateError::NoMasterXmlFound.into()),
+    };
+
+    info!("Validating song pack \"{}\" with id {:03} ({} songs)", &pack_name, pack_id, songs.len());
+
+    let mut report = PackValidationReport {
+        pack_name,
+        pack_id,
+        song_count: songs.len(),
+        issues: Vec::new(),
+    };
+
+    if pack_id < 4 || pack_id > 98 {
+        report.issues.push(ValidationIssue {
+            severity: IssueSeverity::Error,
+            song_id: None,
+            file_name: Some(String::from("master.xml")),
+            message: format!("Pack id value of {} is not valid (must be between 4-98)", pack_id),
+        });
+    }
+
+    // Check for duplicate + out of range ids
+    let mut song_ids = HashSet::new();
+    for song in songs.iter() {
+        let message = match song.song_id {
+            id if id > MAX_SONG_ID => format!("Song id {} is not valid (must be between 0-{})", id, MAX_SONG_ID),
+            id if !song_ids.insert(id) => format!("Song id {} is used more than once", id),
+            _ => continue,
+        };
+
+        report.issues.push(ValidationIssue {
+            severity: IssueSeverity::Error,
+            song_id: Some(song.song_id),
+            file_name: Some(String::from("master.xml")),
+            message,
+        });
+    }
+
+    // Check for song directories missing from master.xml
+    for dir_name in get_song_dir_names(pack_dir)? {
+        if dir_name.parse::<u16>().map(|id| song_ids.contains(&id)).unwrap_or(true) {
+            continue;
+        }
+
+        report.issues.push(ValidationIssue {
+            severity: IssueSeverity::Warning,
+            song_id: None,
+            file_name: Some(dir_name.to_owned()),
+            message: format!("Song directory \"{}\" is not listed in master.xml", dir_name),
+        });
+    }
+
+    // Validate song files in parallel
+    let mut song_issues = songs
+        .par_iter()
+        .map(|song| validate_song(pack_dir, pack_id, song.song_id))
+        .collect::<Vec<_>>();
+
+    song_issues.sort_by_key(|v| v.song_id);
+
+    for validator in song_issues {
+        report.issues.extend(validator.issues);
+    }
+
+    Ok(report)
+}
+
+fn get_song_dir_names(pack_dir: &Path) -> Result<Vec<String>, PraiseError> {
+    let mut dir_names = read_dir(pack_dir)?
+        .filter_map(|d| d.ok())
+        .filter(|d| d.path().is_dir())
+        .filter_map(|d| d.file_name().to_str().map(|n| n.to_owned()))
+        .filter(|n| n.len() == 3 && n.chars().all(|c| c.is_ascii_digit()))
+        .collect::<Vec<_>>();
+
+    dir_names.sort();
+    Ok(dir_names)
+}
+
+fn validate_song(pack_dir: &Path, pack_id: u8, song_id: u16) -> SongValidator {
+    let mut validator = SongValidator {
+        song_id,
+        issues: Vec::new(),
+    };
+
+    let full_song_id = format!("{:02}{:03}", pack_id, song_id);
+    let song_dir = pack_dir.join(format!("{:03}", song_id));
+
+    if !song_dir.is_dir() {
+        validator.add_issue(IssueSeverity::Error, None, format!("Song directory \"{:03}\" not found", song_id));
+        return validator;
+    }
+
+    // Check audio
+    let audio_length = validate_audio(&mut validator, &song_dir, &format!("GPM{}.dpo", full_song_id));
+    validate_audio(&mut validator, &song_dir, &format!("GPP{}.dpo", full_song_id));
+
+    for i in 0..4 {
+        validate_audio(&mut validator, &song_dir, &format!("GPG{}_{}.dpo", full_song_id, i));
+    }
+
+    for i in 0..2 {
+        validate_audio(&mut validator, &song_dir, &format!("GPB{}_{}.dpo", full_song_id, i));
+    }
+
+    // Check art (optional)
+    let art_name = format!("GPC{}.png", full_song_id);
+    match read(song_dir.join(&art_name)) {
+        Ok(data) if data.starts_with(&PNG_SIGNATURE) => {},
+        Ok(_) => validator.add_error(&art_name, String::from("Album art is not a valid png")),
+        Err(_) => validator.add_issue(IssueSeverity::Warning, Some(&art_name), String::from("Album art not found")),
+    }
+
+    // Check notes
+    for track in ["GPG", "GPB"] {
+        for i in 0..4 {
+            let xml_name = format!("{}{}_{}.xml", track, full_song_id, i);
+
+            if let Some(XmlTrack::GuitarBass(beats)) = validate_xml(&mut validator, &song_dir, &xml_name, XmlTrackType::Guitar) {
+                let positions = beats
+                    .iter()
+                    .map(|b| (b.pos, b.length))
+                    .collect::<Vec<_>>();
+
+                validate_positions(&mut validator, &xml_name, &positions, audio_length);
+            }
+        }
+    }
+
+    let lyrics_name = format!("GPL{}.xml", full_song_id);
+    if let Some(XmlTrack::Vocals(lyrics)) = validate_xml(&mut validator, &song_dir, &lyrics_name, XmlTrackType::Vocals) {
+        let positions = lyrics
+            .iter()
+            .map(|l| (l.pos, l.length))
+            .collect::<Vec<_>>();
+
+        validate_positions(&mut validator, &lyrics_name, &positions, audio_length);
+    }
+
+    validator
+}
+
+fn validate_audio(validator: &mut SongValidator, song_dir: &Path, file_name: &str) -> Option<f64> {
+    let file_path = song_dir.join(file_name);
+
+    if !file_path.exists() {
+        validator.add_error(file_name, String::from("Audio file not found"));
+        return None;
+    }
+
+    match get_dpo_length_in_ms(&file_path) {
+        Ok(length) => Some(length),
+        Err(err) => {
+            validator.add_error(file_name, format!("Audio doesn't decode as valid ogg ({})", err));
+            None
+        }
+    }
+}
+
+fn validate_xml(validator: &mut SongValidator, song_dir: &Path, file_name: &str, track_type: XmlTrackType) -> Option<XmlTrack> {
+    let file_path = song_dir.join(file_name);
+
+    if !file_path.exists() {
+        validator.add_error(file_name, String::from("Notes file not found"));
+        return None;
+    }
+
+    let xml_file = match XmlFile::from_path(&file_path) {
+        Ok(xml_file) => xml_file,
+        Err(err) => {
+            validator.add_error(file_name, format!("Can't parse notes ({})", err));
+            return None;
+        }
+    };
+
+    match (track_type, xml_file.track) {
+        (XmlTrackType::Vocals, track @ XmlTrack::Vocals(_)) => Some(track),
+        (XmlTrackType::Guitar | XmlTrackType::Bass, track @ XmlTrack::GuitarBass(_)) => Some(track),
+        _ => {
+            validator.add_error(file_name, String::from("Notes file contains wrong track type"));
+            None
+        }
+    }
+}
+
+fn validate_positions(validator: &mut SongValidator, file_name: &str, positions: &[(u64, u64)], audio_length: Option<f64>) {
+    // Only first issue of each kind is reported
+    if let Some(window) = positions.windows(2).find(|w| w[1].0 < w[0].0) {
+        validator.add_error(file_name, format!("Events aren't sorted (event at {}ms comes after event at {}ms)", window[1].0, window[0].0));
+    }
+
+    let audio_length = match audio_length {
+        Some(length) => length,
+        None => return,
+    };
+
+    if let Some((pos, length)) = positions.iter().find(|(pos, length)| (pos + length) as f64 > audio_length) {
+        validator.add_error(file_name, format!("Event at {}ms ends at {}ms which is after end of audio ({:.0}ms)", pos, pos + length, audio_length));
+    }
+}
@@ -1,24 +1,123 @@
-use std::error::Error;
-use std::fs::{DirEntry, read_dir};
+use crate::PraiseError;
+use crate::dta::SongsDta;
+use crate::vfs::{FileSource, SongSources, ZipSource, SONGS_DTA_FILE_NAME};
+use log::warn;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::{DirEntry, File, read_dir};
+use std::io::BufWriter;
 use std::path::{Path, PathBuf};
 use walkdir::{DirEntry as WalkDirEntry, WalkDir};
 
-pub fn find_dirs_with_file_name(dir_path: &str, file_name: &str) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+pub fn find_dirs_with_file_name(dir_path: &str, file_name: &str) -> Result<Vec<PathBuf>, PraiseError> {
     Ok(WalkDir::new(dir_path)
         .into_iter()
-        .filter(|e| match e {
-            Ok(e) => does_entry_match_file_name(e, file_name),
-            Err(_) => false,
-        })
-        .map(|d| d
-            .unwrap()
+        .filter_map(|e| e.ok())
+        .filter(|e| does_entry_match_file_name(e, file_name))
+        .filter_map(|e| e
             .path()
             .parent()
-            .unwrap()
-            .to_owned())
+            .map(|p| p.to_owned()))
+        .collect())
+}
+
+pub const SONG_ARCHIVE_EXTENSIONS: [&str; 2] = ["sng", "zip"];
+
+// Zip archives need at least one of these to be considered a song
+pub const ZIP_SONG_FILE_NAMES: [&str; 3] = ["song.ini", "notes.chart", "notes.mid"];
+
+pub fn find_song_sources(dir_path: &str) -> Result<SongSources, PraiseError> {
+    // Find CH song directories + song archives (.sng, .zip)
+    let mut song_paths = find_dirs_with_file_name(dir_path, "song.ini")?;
+    let mut dta_songs = HashMap::new();
+
+    // Rock Band song directories listed in songs.dta
+    for dta_path in find_files_with_name(dir_path, SONGS_DTA_FILE_NAME)? {
+        let songs_dir = match dta_path.parent() {
+            Some(dir) => dir,
+            None => continue,
+        };
+
+        let songs_dta = match SongsDta::from_path(&dta_path) {
+            Ok(songs_dta) => songs_dta,
+            Err(err) => {
+                warn!("Can't parse \"{}\" ({})", dta_path.display(), err);
+                continue;
+            }
+        };
+
+        // Parsed entry is kept so songs.dta isn't read again for each song
+        for song in songs_dta.songs.into_iter() {
+            let song_dir = songs_dir.join(song.get_dir_name());
+
+            if song_dir.is_dir() && !song_dir.join("song.ini").exists() && !dta_songs.contains_key(&song_dir) {
+                song_paths.push(song_dir.to_owned());
+                dta_songs.insert(song_dir, song);
+            }
+        }
+    }
+
+    // Archives inside song directories are extras (i.e. backups), not separate songs
+    let song_dir_count = song_paths.len();
+
+    for ext in SONG_ARCHIVE_EXTENSIONS {
+        for archive_path in find_files_with_ext(dir_path, ext)? {
+            if song_paths[..song_dir_count].iter().any(|d| archive_path.starts_with(d)) {
+                continue;
+            }
+
+            if ext == "zip" && !is_song_zip(&archive_path) {
+                continue;
+            }
+
+            song_paths.push(archive_path);
+        }
+    }
+
+    Ok(SongSources {
+        paths: song_paths,
+        dta_songs,
+    })
+}
+
+fn is_song_zip(zip_path: &Path) -> bool {
+    match ZipSource::from_path(zip_path) {
+        Ok(zip_source) => ZIP_SONG_FILE_NAMES
+            .iter()
+            .any(|f| zip_source.contains_file(f)),
+        Err(err) => {
+            warn!("Can't open \"{}\" ({})", zip_path.display(), err);
+            false
+        }
+    }
+}
+
+pub fn find_files_with_name(dir_path: &str, file_name: &str) -> Result<Vec<PathBuf>, PraiseError> {
+    Ok(WalkDir::new(dir_path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && does_entry_match_file_name(e, file_name))
+        .map(|e| e.path().to_owned())
+        .collect())
+}
+
+pub fn find_files_with_ext(dir_path: &str, ext: &str) -> Result<Vec<PathBuf>, PraiseError> {
+    Ok(WalkDir::new(dir_path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && does_entry_match_ext(e, ext))
+        .map(|e| e.path().to_owned())
         .collect())
 }
 
+fn does_entry_match_ext(entry: &WalkDirEntry, ext: &str) -> bool {
+    entry.path()
+         .extension()
+         .and_then(|e| e.to_str())
+         .map(|s| s.eq_ignore_ascii_case(ext))
+         .unwrap_or(false)
+}
+
 fn does_entry_match_file_name(entry: &WalkDirEntry, file_name: &str) -> bool {
     entry.file_name()
          .to_str()
@@ -26,11 +125,10 @@ fn does_entry_match_file_name(entry: &WalkDirEntry, file_name: &str) -> bool {
          .unwrap_or(false)
 }
 
-pub fn get_files_in_dir<T: AsRef<Path>>(dir_path: T, ext: Option<&str>) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+pub fn get_files_in_dir<T: AsRef<Path>>(dir_path: T, ext: Option<&str>) -> Result<Vec<PathBuf>, PraiseError> {
     // Get file paths in given directory
     Ok(read_dir(dir_path)?
-        .filter(|d| d.is_ok())
-        .map(|d| d.unwrap())
+        .filter_map(|d| d.ok())
         .filter(|d| d.path().is_file())
         .map(|d| d.path())
         .filter(|p| match ext {
@@ -46,4 +144,14 @@ pub fn get_files_in_dir<T: AsRef<Path>>(dir_path: T, ext: Option<&str>) -> Resul
             None => true,
         })
         .collect())
-}
\ No newline at end of file
+}
+
+pub(crate) fn write_json_file<T: Serialize, P: AsRef<Path>>(value: &T, path: P) -> Result<(), PraiseError> {
+    let file = File::create(path)?;
+    let writer = BufWriter::new(file);
+
+    serde_json::to_writer_pretty(writer, value)
+        .map_err(std::io::Error::from)?;
+
+    Ok(())
+}
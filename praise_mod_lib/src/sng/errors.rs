@@ -0,0 +1,23 @@
+use thiserror::Error as ThisError;
+
+#[derive(Debug, ThisError)]
+pub enum SngReadError {
+    #[error("Can't open sng file because of \"{text}\"")]
+    CantOpenSngFile {
+        text: String,
+    },
+    #[error("Invalid sng header")]
+    InvalidHeader,
+    #[error("Sng version {version} is not supported")]
+    UnsupportedVersion {
+        version: u32,
+    },
+    #[error("Unexpected end of sng data at offset {offset}")]
+    UnexpectedEndOfData {
+        offset: usize,
+    },
+    #[error("Sng file \"{name}\" is outside of file data")]
+    InvalidFileRange {
+        name: String,
+    },
+}
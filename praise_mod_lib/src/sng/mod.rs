@@ -0,0 +1,5 @@
+mod errors;
+mod sng_file;
+
+pub use self::errors::*;
+pub use self::sng_file::*;
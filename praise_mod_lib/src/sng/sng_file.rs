@@ -0,0 +1,180 @@
+use crate::sng::SngReadError;
+use crate::vfs::{FileSource, file_not_found};
+use std::collections::HashMap;
+use std::fs::read;
+use std::io;
+use std::path::Path;
+
+const SNG_MAGIC: &[u8; 6] = b"SNGPKG";
+const SNG_VERSION: u32 = 1;
+
+#[derive(Clone, Debug)]
+pub struct SngAsset {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SngFile {
+    pub version: u32,
+    pub metadata: HashMap<String, String>, // song.ini values
+    pub files: Vec<SngAsset>,
+}
+
+struct SngCursor<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> SngCursor<'a> {
+    fn read_bytes(&mut self, count: usize) -> Result<&'a [u8], SngReadError> {
+        if self.data.len() - self.pos < count {
+            return Err(SngReadError::UnexpectedEndOfData {
+                offset: self.data.len(),
+            });
+        }
+
+        let bytes = &self.data[self.pos..(self.pos + count)];
+        self.pos += count;
+
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, SngReadError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_u32(&mut self) -> Result<u32, SngReadError> {
+        let bytes = self.read_bytes(4)?;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn read_u64(&mut self) -> Result<u64, SngReadError> {
+        let bytes = self.read_bytes(8)?;
+        Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn read_string(&mut self, length: usize) -> Result<String, SngReadError> {
+        let bytes = self.read_bytes(length)?;
+        Ok(String::from_utf8_lossy(bytes).into_owned())
+    }
+}
+
+impl SngFile {
+    pub fn from_path<T: AsRef<Path>>(sng_path: T) -> Result<SngFile, SngReadError> {
+        let data = read(sng_path)
+            .map_err(|err| SngReadError::CantOpenSngFile {
+                text: err.to_string(),
+            })?;
+
+        SngFile::from_bytes(&data)
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<SngFile, SngReadError> {
+        let mut cursor = SngCursor {
+            data,
+            pos: 0,
+        };
+
+        // Parse header
+        let magic = cursor.read_bytes(SNG_MAGIC.len())
+            .map_err(|_| SngReadError::InvalidHeader)?;
+
+        if magic != SNG_MAGIC {
+            return Err(SngReadError::InvalidHeader);
+        }
+
+        let version = cursor.read_u32()?;
+        if version != SNG_VERSION {
+            return Err(SngReadError::UnsupportedVersion { version });
+        }
+
+        let xor_mask = cursor.read_bytes(16)?;
+
+        // Parse metadata (key/value pairs)
+        let _metadata_size = cursor.read_u64()?;
+        let metadata_count = cursor.read_u64()?;
+        let mut metadata = HashMap::new();
+
+        for _ in 0..metadata_count {
+            let key_length = cursor.read_u32()? as usize;
+            let key = cursor.read_string(key_length)?;
+
+            let value_length = cursor.read_u32()? as usize;
+            let value = cursor.read_string(value_length)?;
+
+            metadata.insert(key, value);
+        }
+
+        // Parse file index
+        let _file_index_size = cursor.read_u64()?;
+        let file_count = cursor.read_u64()?;
+        let mut file_entries = Vec::new();
+
+        for _ in 0..file_count {
+            let name_length = cursor.read_u8()? as usize;
+            let name = cursor.read_string(name_length)?;
+
+            let size = cursor.read_u64()?;
+            let offset = cursor.read_u64()?; // Absolute offset
+
+            file_entries.push((name, offset, size));
+        }
+
+        // Read + unmask file data
+        let files = file_entries
+            .into_iter()
+            .map(|(name, offset, size)| {
+                let start = offset as usize;
+                let end = start.checked_add(size as usize);
+
+                let masked_data = match end {
+                    Some(end) if end <= data.len() => &data[start..end],
+                    _ => return Err(SngReadError::InvalidFileRange { name }),
+                };
+
+                let data = masked_data
+                    .iter()
+                    .enumerate()
+                    .map(|(i, b)| b ^ (xor_mask[i & 0x0F] ^ (i & 0xFF) as u8))
+                    .collect();
+
+                Ok(SngAsset {
+                    name,
+                    data,
+                })
+            })
+            .collect::<Result<Vec<SngAsset>, SngReadError>>()?;
+
+        Ok(SngFile {
+            version,
+            metadata,
+            files,
+        })
+    }
+
+    pub fn get_file<'a>(&'a self, name: &str) -> Option<&'a SngAsset> {
+        self.files
+            .iter()
+            .find(|f| f.name.eq_ignore_ascii_case(name))
+    }
+}
+
+impl FileSource for SngFile {
+    fn get_file_names(&self) -> Vec<String> {
+        self.files
+            .iter()
+            .map(|f| f.name.to_owned())
+            .collect()
+    }
+
+    fn read_file(&self, file_name: &str) -> io::Result<Vec<u8>> {
+        self.get_file(file_name)
+            .map(|f| f.data.to_owned())
+            .ok_or_else(|| file_not_found(file_name))
+    }
+
+    fn get_metadata(&self) -> Option<&HashMap<String, String>> {
+        Some(&self.metadata)
+    }
+}
@@ -0,0 +1,15 @@
+use thiserror::Error as ThisError;
+
+#[derive(Debug, ThisError)]
+pub enum SongMetaError {
+    #[error("Can't open song.ini because of \"{text}\"")]
+    CantOpenSongIni {
+        text: String,
+    },
+    #[error("Can't parse song.ini because of \"{text}\"")]
+    CantParseSongIni {
+        text: String,
+    },
+    #[error("No \"[song]\" section found in song.ini")]
+    MissingSongSection,
+}
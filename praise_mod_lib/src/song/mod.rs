@@ -1,3 +1,5 @@
+mod errors;
 mod song_meta;
 
-pub use self::song_meta::*;
\ No newline at end of file
+pub use self::errors::*;
+pub use self::song_meta::*;
@@ -1,24 +1,79 @@
+use crate::PraiseError;
+use crate::song::SongMetaError;
+use crate::vfs::FileSource;
 use ini::Ini;
-use log::{info};
-use std::io::Read;
-use std::error::Error;
-use std::fs::{File};
-use std::path::{Path, PathBuf};
+use std::collections::HashMap;
+use std::fs::read;
+use std::path::Path;
 
-#[derive(Clone, Debug)]
+// Keys read into SongMeta fields
+const KNOWN_KEYS: [&str; 20] = [
+    "name", "artist", "album", "year", "genre", "charter", "frets", "icon", "loading_phrase",
+    "song_length", "preview_start_time", "preview_end_time", "delay", "diff_guitar", "diff_bass",
+    "album_track", "track", "playlist_track", "hopo_frequency", "song_id",
+];
+
+#[derive(Clone, Debug, Default)]
 pub struct SongMeta {
     pub name: String,
     pub artist: String,
     pub album: String,
     pub year: i32,
+    pub genre: String,
+    pub charter: String,
+    pub icon: String,
+    pub loading_phrase: String,
+    pub song_length: Option<u32>, // ms
     pub preview_start: Option<u32>, // ms
+    pub preview_end: Option<u32>, // ms
+    pub delay: Option<i32>, // ms
+    pub diff_guitar: Option<i32>, // 0-6, -1 = not charted
+    pub diff_bass: Option<i32>, // 0-6, -1 = not charted
+    pub album_track: Option<u32>,
+    pub playlist_track: Option<u32>,
+    pub hopo_frequency: Option<u32>, // Ticks
+    pub song_id: Option<String>, // Rock Band song id
+    pub other: HashMap<String, String>, // Unknown keys
 }
 
 impl SongMeta {
-    pub fn from_path(ini_path: &Path) -> Result<SongMeta, Box<dyn Error>> {
-        // TODO: Throw error if ini not found or "song" section not present
-        let song_ini = Ini::load_from_file(ini_path)?;
+    pub fn from_path(ini_path: &Path) -> Result<SongMeta, PraiseError> {
+        let ini_data = read(ini_path)
+            .map_err(|err| SongMetaError::CantOpenSongIni {
+                text: err.to_string(),
+            })?;
+
+        SongMeta::from_bytes(&ini_data)
+    }
+
+    pub fn from_source(source: &dyn FileSource) -> Result<SongMeta, PraiseError> {
+        // Metadata is stored in container (.sng)
+        if let Some(metadata) = source.get_metadata() {
+            return Ok(SongMeta::from_metadata(metadata));
+        }
+
+        let ini_data = source
+            .read_file("song.ini")
+            .map_err(|err| SongMetaError::CantOpenSongIni {
+                text: err.to_string(),
+            })?;
+
+        SongMeta::from_bytes(&ini_data)
+    }
+
+    pub fn from_bytes(ini_data: &[u8]) -> Result<SongMeta, PraiseError> {
+        let ini_text = String::from_utf8_lossy(ini_data);
+
+        // Ignore byte order mark if present
+        let song_ini = Ini::load_from_str(ini_text.trim_start_matches('\u{feff}'))
+            .map_err(|err| SongMetaError::CantParseSongIni {
+                text: err.to_string(),
+            })?;
 
+        Ok(SongMeta::from_ini(&song_ini)?)
+    }
+
+    fn from_ini(song_ini: &Ini) -> Result<SongMeta, SongMetaError> {
         // Can be either "song" or "Song"
         let song_section_name = song_ini
             .sections()
@@ -27,38 +82,87 @@ impl SongMeta {
                 None => false,
             });
 
-        let song_section_name = song_section_name
-            .unwrap()
-            .unwrap();
+        let song_section = song_section_name
+            .flatten()
+            .and_then(|name| song_ini.section(Some(name)))
+            .ok_or(SongMetaError::MissingSongSection)?;
 
-        let song_section = song_ini
-            .section(Some(song_section_name))
-            .unwrap();
+        let metadata = song_section
+            .iter()
+            .map(|(k, v)| (k.to_owned(), v.to_owned()))
+            .collect::<HashMap<_, _>>();
 
-        Ok(SongMeta {
-            name: match song_section.get("name") {
+        Ok(SongMeta::from_metadata(&metadata))
+    }
+
+    pub fn from_metadata(metadata: &HashMap<String, String>) -> SongMeta {
+        let mut song_meta = SongMeta::from_getter(|key| metadata
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str()));
+
+        // Keep unknown keys so callers can still read them
+        song_meta.other = metadata
+            .iter()
+            .filter(|(k, _)| !KNOWN_KEYS.iter().any(|known| k.eq_ignore_ascii_case(known)))
+            .map(|(k, v)| (k.to_lowercase(), v.to_owned()))
+            .collect();
+
+        song_meta
+    }
+
+    pub fn get_other(&self, key: &str) -> Option<&str> {
+        self.other
+            .get(&key.to_lowercase())
+            .map(|v| v.as_str())
+    }
+
+    fn from_getter<'a, F: Fn(&str) -> Option<&'a str>>(get: F) -> SongMeta {
+        let get_number = |key: &str| get(key).and_then(|text| text.trim().parse().ok());
+        let get_signed = |key: &str| get(key).and_then(|text| text.trim().parse().ok());
+        let get_text = |key: &str| get(key).map(|text| text.to_owned()).unwrap_or_default();
+
+        SongMeta {
+            name: match get("name") {
                 Some(text) => text.to_owned(),
                 None => String::from(""),
             },
-            artist: match song_section.get("artist") {
+            artist: match get("artist") {
                 Some(text) => text.to_owned(),
                 None => String::from(""),
             },
-            album: match song_section.get("album") {
+            album: match get("album") {
                 Some(text) => text.to_owned(),
                 None => String::from(""),
             },
-            year: match song_section.get("year") {
+            year: match get("year") {
                 Some(text) => text.parse().unwrap_or(2020),
                 None => 2020,
             },
-            preview_start: match song_section.get("preview_start_time") {
+            genre: get_text("genre"),
+            charter: match get("charter") {
+                Some(text) => text.to_owned(),
+                None => get_text("frets"), // Older name
+            },
+            icon: get_text("icon"),
+            loading_phrase: get_text("loading_phrase"),
+            song_length: get_number("song_length"),
+            preview_start: match get("preview_start_time") {
                 Some(text) => match text.parse() {
                     Ok(time) => Some(time),
                     Err(_) => None
                 },
                 None => None,
-            }
-        })
+            },
+            preview_end: get_number("preview_end_time"),
+            delay: get_signed("delay"),
+            diff_guitar: get_signed("diff_guitar"),
+            diff_bass: get_signed("diff_bass"),
+            album_track: get_number("album_track").or_else(|| get_number("track")),
+            playlist_track: get_number("playlist_track"),
+            hopo_frequency: get_number("hopo_frequency"),
+            song_id: get("song_id").map(|text| text.to_owned()),
+            other: HashMap::new(),
+        }
     }
 }
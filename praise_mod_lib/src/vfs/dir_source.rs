@@ -0,0 +1,46 @@
+use crate::shared::get_files_in_dir;
+use crate::vfs::{FileSource, file_not_found};
+use std::fs::read;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub struct DirectorySource {
+    path: PathBuf,
+    file_names: Vec<String>, // Listed once when opened
+}
+
+impl DirectorySource {
+    pub fn new<T: AsRef<Path>>(path: T) -> DirectorySource {
+        let path = path.as_ref().to_owned();
+
+        let file_names = get_files_in_dir(&path, None)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|p| p
+                .file_name()
+                .and_then(|f| f.to_str())
+                .map(|f| f.to_string()))
+            .collect();
+
+        DirectorySource {
+            path,
+            file_names,
+        }
+    }
+}
+
+impl FileSource for DirectorySource {
+    fn get_file_names(&self) -> Vec<String> {
+        self.file_names.to_owned()
+    }
+
+    fn read_file(&self, file_name: &str) -> io::Result<Vec<u8>> {
+        // Match file name case insensitive
+        let file_name = self.file_names
+            .iter()
+            .find(|f| f.eq_ignore_ascii_case(file_name))
+            .ok_or_else(|| file_not_found(file_name))?;
+
+        read(self.path.join(file_name))
+    }
+}
@@ -0,0 +1,49 @@
+use crate::dta::DtaSong;
+use crate::vfs::{DirectorySource, FileSource};
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+
+pub const SONGS_DTA_FILE_NAME: &str = "songs.dta";
+
+// Rock Band song folder described by parent songs.dta
+pub struct DtaSongSource {
+    dir: DirectorySource,
+    metadata: HashMap<String, String>,
+    midi_file_name: String,
+}
+
+impl DtaSongSource {
+    pub fn new<T: AsRef<Path>>(song_dir: T, song: &DtaSong) -> DtaSongSource {
+        DtaSongSource {
+            dir: DirectorySource::new(song_dir),
+            metadata: song.metadata.to_owned(),
+            midi_file_name: song.get_midi_file_name(),
+        }
+    }
+}
+
+impl FileSource for DtaSongSource {
+    fn get_file_names(&self) -> Vec<String> {
+        // Expose midi using CH name
+        self.dir.get_file_names()
+            .into_iter()
+            .map(|f| match f.eq_ignore_ascii_case(&self.midi_file_name) {
+                true => String::from("notes.mid"),
+                false => f,
+            })
+            .collect()
+    }
+
+    fn read_file(&self, file_name: &str) -> io::Result<Vec<u8>> {
+        if file_name.eq_ignore_ascii_case("notes.mid") {
+            return self.dir.read_file(&self.midi_file_name);
+        }
+
+        self.dir.read_file(file_name)
+    }
+
+    fn get_metadata(&self) -> Option<&HashMap<String, String>> {
+        Some(&self.metadata)
+    }
+}
\ No newline at end of file
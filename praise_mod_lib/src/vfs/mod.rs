@@ -0,0 +1,78 @@
+mod dir_source;
+mod dta_source;
+mod zip_source;
+
+pub use self::dir_source::*;
+pub use self::dta_source::*;
+pub use self::zip_source::*;
+
+use crate::PraiseError;
+use crate::dta::DtaSong;
+use crate::sng::SngFile;
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Read-only collection of song files (directory, archive, etc.)
+pub trait FileSource {
+    fn get_file_names(&self) -> Vec<String>;
+    fn read_file(&self, file_name: &str) -> io::Result<Vec<u8>>;
+
+    // song.ini values if stored outside of files (i.e. .sng)
+    fn get_metadata(&self) -> Option<&HashMap<String, String>> {
+        None
+    }
+
+    fn contains_file(&self, file_name: &str) -> bool {
+        self.get_file_names()
+            .iter()
+            .any(|f| f.eq_ignore_ascii_case(file_name))
+    }
+}
+
+// Songs found in songs directory
+#[derive(Debug, Default)]
+pub struct SongSources {
+    pub paths: Vec<PathBuf>,
+    pub dta_songs: HashMap<PathBuf, DtaSong>, // Rock Band song directory -> songs.dta entry
+}
+
+impl SongSources {
+    pub fn open<T: AsRef<Path>>(&self, path: T) -> Result<Box<dyn FileSource>, PraiseError> {
+        let path = path.as_ref();
+
+        // Rock Band songs don't have song.ini
+        match self.dta_songs.get(path) {
+            Some(song) => Ok(Box::new(DtaSongSource::new(path, song))),
+            None => open_file_source(path),
+        }
+    }
+}
+
+pub fn open_file_source<T: AsRef<Path>>(path: T) -> Result<Box<dyn FileSource>, PraiseError> {
+    let path = path.as_ref();
+
+    if path.is_dir() {
+        return Ok(Box::new(DirectorySource::new(path)));
+    }
+
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+
+    if ext.eq_ignore_ascii_case("sng") {
+        Ok(Box::new(SngFile::from_path(path)?))
+    } else if ext.eq_ignore_ascii_case("zip") {
+        Ok(Box::new(ZipSource::from_path(path)?))
+    } else {
+        Err(PraiseError::IO(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("Unsupported song source \"{}\"", path.display()),
+        )))
+    }
+}
+
+pub(crate) fn file_not_found(file_name: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("File \"{}\" not found", file_name))
+}
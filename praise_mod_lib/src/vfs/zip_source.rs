@@ -0,0 +1,82 @@
+use crate::vfs::{FileSource, file_not_found};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::sync::Mutex;
+use zip::ZipArchive;
+use zip::result::ZipError;
+
+// Zip archive with single song (can be nested in sub directory)
+pub struct ZipSource {
+    archive: Mutex<ZipArchive<File>>,
+    root_dir: String,
+    file_names: Vec<String>,
+}
+
+impl ZipSource {
+    pub fn from_path<T: AsRef<Path>>(zip_path: T) -> Result<ZipSource, ZipError> {
+        let zip_file = File::open(zip_path)?;
+        let archive = ZipArchive::new(zip_file)?;
+
+        let entry_names = archive
+            .file_names()
+            .map(|n| n.to_string())
+            .collect::<Vec<String>>();
+
+        // Use directory with song.ini as root, otherwise top level
+        let root_dir = entry_names
+            .iter()
+            .filter(|n| get_base_name(n).eq_ignore_ascii_case("song.ini"))
+            .map(|n| n[..(n.len() - "song.ini".len())].to_string())
+            .min_by_key(|n| n.len())
+            .unwrap_or_default();
+
+        // Only keep files directly in root directory
+        let file_names = entry_names
+            .iter()
+            .filter_map(|n| n.strip_prefix(&root_dir))
+            .filter(|n| !n.is_empty() && !n.contains('/'))
+            .map(|n| n.to_string())
+            .collect();
+
+        Ok(ZipSource {
+            archive: Mutex::new(archive),
+            root_dir,
+            file_names,
+        })
+    }
+}
+
+impl FileSource for ZipSource {
+    fn get_file_names(&self) -> Vec<String> {
+        self.file_names.to_owned()
+    }
+
+    fn read_file(&self, file_name: &str) -> io::Result<Vec<u8>> {
+        // Match file name case insensitive
+        let file_name = self.file_names
+            .iter()
+            .find(|f| f.eq_ignore_ascii_case(file_name))
+            .ok_or_else(|| file_not_found(file_name))?;
+
+        let mut archive = self.archive
+            .lock()
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Zip archive lock poisoned"))?;
+
+        let mut zip_file = archive
+            .by_name(&format!("{}{}", &self.root_dir, file_name))
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+
+        let mut data = Vec::new();
+        zip_file.read_to_end(&mut data)?;
+
+        Ok(data)
+    }
+}
+
+fn get_base_name(entry_name: &str) -> &str {
+    match entry_name.rfind('/') {
+        Some(i) => &entry_name[(i + 1)..],
+        None => entry_name,
+    }
+}
@@ -0,0 +1,20 @@
+use thiserror::Error as ThisError;
+
+#[derive(Debug, ThisError)]
+pub enum XmlParseError {
+    #[error("Can't open xml file because of \"{text}\"")]
+    CantOpenXmlFile {
+        text: String,
+    },
+    #[error("No root element found")]
+    MissingRootElement,
+    #[error("Unsupported root element \"<{name}>\"")]
+    UnsupportedRootElement {
+        name: String,
+    },
+    #[error("Can't parse \"{value}\" as time for \"<{tag_name}>\"")]
+    InvalidTimeValue {
+        tag_name: String,
+        value: String,
+    },
+}
@@ -150,7 +150,76 @@ fn get_track_by_name<'a>(chart: &'a SongChart, track_name: &str) -> Option<&'a G
 }
 
 fn parse_vocal_track_from_chart(chart: &SongChart) -> XmlTrack {
-    XmlTrack::Vocals(Vec::new())
+    let mut lyrics = Vec::new();
+
+    // (start pos, lyric text)
+    let mut current_phrase: Option<(f64, Vec<String>)> = None;
+    let mut last_lyric_end = 0.0;
+
+    for event in chart.event_track.events.iter() {
+        match &event.value {
+            TextEventType::PhraseStart => {
+                // New phrase also ends previous phrase
+                if let Some((start_pos, split_text)) = current_phrase.take() {
+                    add_lyric_phrase(&mut lyrics, start_pos, event.pos_realtime, &split_text);
+                }
+
+                current_phrase = Some((event.pos_realtime, Vec::new()));
+            },
+            TextEventType::PhraseEnd => {
+                if let Some((start_pos, split_text)) = current_phrase.take() {
+                    add_lyric_phrase(&mut lyrics, start_pos, event.pos_realtime, &split_text);
+                }
+            },
+            TextEventType::Lyric(text) => {
+                // Ignore lyrics outside of phrases
+                if let Some((_, split_text)) = &mut current_phrase {
+                    split_text.push(text.to_owned());
+
+                    // Chart lyrics have no length, assume they last one beat
+                    last_lyric_end = event.pos_realtime + get_beat_length_realtime(chart, event.pos);
+                }
+            },
+            _ => continue,
+        }
+    }
+
+    if let Some((start_pos, split_text)) = current_phrase.take() {
+        // Phrase never ended, use end of last lyric
+        add_lyric_phrase(&mut lyrics, start_pos, last_lyric_end, &split_text);
+    }
+
+    XmlTrack::Vocals(lyrics)
+}
+
+fn get_beat_length_realtime(chart: &SongChart, pos: u64) -> f64 {
+    let bpm = chart
+        .sync_track
+        .events
+        .iter()
+        .take_while(|ev| ev.pos <= pos)
+        .filter_map(|ev| match ev.value {
+            SyncEventType::Beat(bpm) if bpm > 0 => Some(bpm),
+            _ => None,
+        })
+        .last()
+        .unwrap_or(120_000); // 120 bpm
+
+    60_000_000.0 / bpm as f64
+}
+
+fn add_lyric_phrase(lyrics: &mut Vec<LyricEvent>, start_pos: f64, end_pos: f64, split_text: &Vec<String>) {
+    if split_text.is_empty() {
+        return;
+    }
+
+    let end_pos = end_pos.max(start_pos);
+
+    lyrics.push(LyricEvent {
+        pos: start_pos as u64,
+        length: (end_pos - start_pos) as u64,
+        text: XmlFile::concat_text(split_text),
+    });
 }
 
 fn get_track_name(ins: GuitarInstrument, diff: XmlTrackDifficulty) -> String {
@@ -168,4 +237,56 @@ fn get_track_name(ins: GuitarInstrument, diff: XmlTrackDifficulty) -> String {
     };
 
     diff_text.to_string() + instrument_name
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_lyrics(events: &str) -> Vec<LyricEvent> {
+        let text = format!("[Song]
+{{
+  Resolution = 192
+}}
+[SyncTrack]
+{{
+  0 = TS 4
+  0 = B 120000
+}}
+[Events]
+{{
+{}
+}}", events);
+
+        let chart = SongChart::from_bytes(text.as_bytes()).unwrap();
+
+        match XmlFile::from_chart(&chart, XmlTrackType::Vocals, None).track {
+            XmlTrack::Vocals(lyrics) => lyrics,
+            _ => panic!("Expected vocal track"),
+        }
+    }
+
+    #[test]
+    fn unterminated_phrase_ends_after_last_lyric() {
+        let lyrics = parse_lyrics("  384 = E \"phrase_start\"
+  384 = E \"lyric Hel-\"
+  576 = E \"lyric lo\"");
+
+        // 192 ticks = 500ms at 120 bpm, last lyric lasts one beat
+        assert_eq!(1, lyrics.len());
+        assert_eq!(1000, lyrics[0].pos);
+        assert_eq!(1000, lyrics[0].length);
+    }
+
+    #[test]
+    fn unterminated_phrase_ends_at_next_phrase_start() {
+        let lyrics = parse_lyrics("  0 = E \"phrase_start\"
+  0 = E \"lyric Hel-\"
+  384 = E \"phrase_start\"
+  384 = E \"lyric lo\"
+  576 = E \"phrase_end\"");
+
+        assert_eq!(2, lyrics.len());
+        assert_eq!((0, 1000), (lyrics[0].pos, lyrics[0].length));
+        assert_eq!((1000, 500), (lyrics[1].pos, lyrics[1].length));
+    }
+}
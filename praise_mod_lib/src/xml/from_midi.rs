@@ -5,6 +5,10 @@ use std::io::{Write, BufReader, BufRead, Error};
 use std::path::{Path, PathBuf};
 use crate::xml::*;
 
+const PS_OPEN_PHRASE: u8 = 0x01;
+const PS_TAP_PHRASE: u8 = 0x04;
+const PS_ALL_DIFFICULTIES: u8 = 0xFF;
+
 impl XmlFile {
     pub fn from_midi(mid: &MidiFile, track_type: XmlTrackType, track_difficulty: Option<XmlTrackDifficulty>) -> XmlFile {
         XmlFile {
@@ -57,6 +61,32 @@ impl XmlFile {
             XmlTrackDifficulty::Expert => 96u8,
         };
         let sp_offset = 116u8;
+        let tap_offset = 104u8;
+        let forced_hopo_offset = notes_offset + 5;
+        let forced_strum_offset = notes_offset + 6;
+
+        // Forced hopo/strum + tap phrases
+        let get_phrases = |pitch: u8| midi_notes
+            .iter()
+            .filter(|note| note.pitch == pitch)
+            .map(|note| (note.pos, note.pos + note.length))
+            .collect::<Vec<(u64, u64)>>();
+
+        let forced_hopo_phrases = get_phrases(forced_hopo_offset);
+        let forced_strum_phrases = get_phrases(forced_strum_offset);
+        let mut tap_phrases = get_phrases(tap_offset);
+
+        // Phase Shift sysex phrases
+        let ps_diff = match track_difficulty {
+            XmlTrackDifficulty::Easy => 0u8,
+            XmlTrackDifficulty::Medium => 1u8,
+            XmlTrackDifficulty::Hard => 2u8,
+            XmlTrackDifficulty::Expert => 3u8,
+        };
+
+        let sysex_events = &guitar_track.unwrap().sysex;
+        tap_phrases.append(&mut get_ps_phrases(sysex_events, ps_diff, PS_TAP_PHRASE));
+        let open_phrases = get_ps_phrases(sysex_events, ps_diff, PS_OPEN_PHRASE);
 
         // Star power notes
         let mut star_power = midi_notes
@@ -71,7 +101,12 @@ impl XmlFile {
             .into_iter()
             .filter(|note|
                 note.pitch >= notes_offset &&
-                note.pitch <= notes_offset + 5) {
+                note.pitch <= notes_offset + 4) {
+            if note.pitch == notes_offset && is_in_phrase(&open_phrases, note.pos) {
+                // Green notes in open phrase are open notes, skip (same as .chart)
+                continue;
+            }
+
             let pos = note.pos_realtime as u64;
             let length = match note.length {
                 l if l > sustain_length  => note.length_realtime as u64,
@@ -95,10 +130,14 @@ impl XmlFile {
                 }
             }
 
+            // Tap notes always take priority over forced strums
+            let is_tap_note = is_in_phrase(&tap_phrases, note.pos)
+                || (is_in_phrase(&forced_hopo_phrases, note.pos) && !is_in_phrase(&forced_strum_phrases, note.pos));
+
             if let Some(beat_event) = &mut current_note {
                 if beat_event.pos == pos {
                     // Is part of chord, update current note
-                    XmlFile::update_fret_beat_event(beat_event, length, note.pitch - notes_offset, is_sp_note, false);
+                    XmlFile::update_fret_beat_event(beat_event, length, note.pitch - notes_offset, is_sp_note, is_tap_note);
                 } else {
                     // Pop off current note and add to collection
                     let beat_event = current_note.take().unwrap();
@@ -109,7 +148,7 @@ impl XmlFile {
             // Add as new note
             if current_note.is_none() {
                 let mut beat_event = BeatEvent::default(pos, length);
-                XmlFile::update_fret_beat_event(&mut beat_event, length, note.pitch - notes_offset, is_sp_note, false);
+                XmlFile::update_fret_beat_event(&mut beat_event, length, note.pitch - notes_offset, is_sp_note, is_tap_note);
 
                 current_note = Some(beat_event);
             }
@@ -194,4 +233,38 @@ impl XmlFile {
 
         XmlTrack::Vocals(lyrics)
     }
-}
\ No newline at end of file
+}
+
+fn get_ps_phrases(sysex_events: &[MidiSysEx], difficulty: u8, phrase_type: u8) -> Vec<(u64, u64)> {
+    let mut phrases = Vec::new();
+    let mut phrase_start: Option<u64> = None;
+
+    // Phase Shift format: 50 53 00 00 <difficulty> <type> <enable> F7
+    for sysex in sysex_events.iter() {
+        let (diff, ps_type, enabled) = match sysex.data.as_slice() {
+            [0x50, 0x53, 0x00, 0x00, diff, ps_type, enabled, ..] => (*diff, *ps_type, *enabled != 0),
+            _ => continue,
+        };
+
+        if ps_type != phrase_type || (diff != difficulty && diff != PS_ALL_DIFFICULTIES) {
+            continue;
+        }
+
+        match (phrase_start, enabled) {
+            (None, true) => phrase_start = Some(sysex.pos),
+            (Some(start), false) => {
+                phrases.push((start, sysex.pos));
+                phrase_start = None;
+            },
+            _ => continue,
+        }
+    }
+
+    phrases
+}
+
+fn is_in_phrase(phrases: &[(u64, u64)], pos: u64) -> bool {
+    phrases
+        .iter()
+        .any(|(start, end)| pos == *start || (pos > *start && pos < *end))
+}
@@ -1,13 +1,21 @@
+mod errors;
 mod from_chart;
 mod from_midi;
+mod ratings;
+mod reader;
 mod shared;
 mod song_meta_builder;
+mod text;
 mod writer;
 mod xml_file;
 
+pub use self::errors::*;
 pub use self::from_chart::*;
 pub use self::from_midi::*;
+pub use self::ratings::*;
+pub use self::reader::*;
 pub use self::shared::*;
 pub use self::song_meta_builder::*;
+pub use self::text::*;
 pub use self::writer::*;
 pub use self::xml_file::*;
\ No newline at end of file
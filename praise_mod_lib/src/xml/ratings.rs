@@ -0,0 +1,176 @@
+use crate::xml::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Bump when rating calculation changes so cached songs are converted again
+pub const RATINGS_VERSION: u32 = 1;
+
+pub const MIN_DIFFICULTY_RATING: u8 = 1;
+pub const MAX_DIFFICULTY_RATING: u8 = 4;
+
+// Game stores tempo as tier code (001 = slow, 002 = medium, 003 = fast, thresholds are a heuristic)
+pub const MIN_BPM_TIER: u8 = 1;
+pub const MAX_BPM_TIER: u8 = 3;
+
+// Notes per second thresholds for each rating (after chord weighting)
+const DIFFICULTY_NPS_THRESHOLDS: [f64; 3] = [2.5, 4.5, 7.0];
+
+// Lowest BPM of each tier above slow
+const BPM_TIER_THRESHOLDS: [u16; 2] = [100, 140];
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct SongRatings {
+    pub difficulties: [u8; 4], // Easy -> Expert
+    pub bpms: [u8; 3], // Tiers of lowest, most common, highest bpm
+}
+
+impl Default for SongRatings {
+    fn default() -> SongRatings {
+        SongRatings {
+            difficulties: [1, 2, 3, 4],
+            bpms: [3, 2, 3],
+        }
+    }
+}
+
+impl SongRatings {
+    pub fn new(diff_guitar: Option<i32>, guitar_tracks: &[&Vec<BeatEvent>], tempo_map: &[(f64, f64)]) -> SongRatings {
+        let song_length = guitar_tracks
+            .iter()
+            .flat_map(|beats| beats.last())
+            .map(|beat| (beat.pos + beat.length) as f64)
+            .fold(0.0, f64::max);
+
+        SongRatings {
+            difficulties: match diff_guitar {
+                Some(diff) if diff >= 0 => get_difficulty_ratings_from_ini(diff),
+                _ => {
+                    let mut ratings = [MIN_DIFFICULTY_RATING; 4];
+
+                    for (rating, beats) in ratings.iter_mut().zip(guitar_tracks.iter()) {
+                        *rating = get_difficulty_rating(beats);
+                    }
+
+                    ratings
+                }
+            },
+            bpms: get_bpm_tiers(tempo_map, song_length),
+        }
+    }
+}
+
+fn get_difficulty_ratings_from_ini(diff_guitar: i32) -> [u8; 4] {
+    // CH difficulty is 0-6
+    let expert = (diff_guitar.min(6) as u8 * (MAX_DIFFICULTY_RATING - MIN_DIFFICULTY_RATING)) / 6 + MIN_DIFFICULTY_RATING;
+
+    [
+        expert.saturating_sub(3).max(MIN_DIFFICULTY_RATING),
+        expert.saturating_sub(2).max(MIN_DIFFICULTY_RATING),
+        expert.saturating_sub(1).max(MIN_DIFFICULTY_RATING),
+        expert,
+    ]
+}
+
+pub fn get_difficulty_rating(beats: &[BeatEvent]) -> u8 {
+    let (first, last) = match (beats.first(), beats.last()) {
+        (Some(first), Some(last)) if last.pos > first.pos => (first, last),
+        _ => return MIN_DIFFICULTY_RATING,
+    };
+
+    let length_secs = (last.pos - first.pos) as f64 / 1000.0;
+    let notes_per_sec = beats.len() as f64 / length_secs;
+
+    // Chords are harder to play than single notes
+    let chord_count = beats
+        .iter()
+        .filter(|b| [b.green, b.red, b.yellow, b.blue, b.orange]
+            .iter()
+            .filter(|f| **f)
+            .count() > 1)
+        .count();
+
+    let chord_ratio = chord_count as f64 / beats.len() as f64;
+    let weighted_nps = notes_per_sec * (1.0 + chord_ratio);
+
+    let rating = DIFFICULTY_NPS_THRESHOLDS
+        .iter()
+        .filter(|t| weighted_nps >= **t)
+        .count() as u8;
+
+    (MIN_DIFFICULTY_RATING + rating).min(MAX_DIFFICULTY_RATING)
+}
+
+pub fn get_bpm_tier(bpm: u16) -> u8 {
+    let tier = BPM_TIER_THRESHOLDS
+        .iter()
+        .filter(|t| bpm >= **t)
+        .count() as u8;
+
+    (MIN_BPM_TIER + tier).min(MAX_BPM_TIER)
+}
+
+// Heuristic: meaning of bpm_easy/medium/hard isn't documented, so tiers of lowest,
+// most common and highest bpm are used (same 001-003 range as original packs)
+pub fn get_bpm_tiers(tempo_map: &[(f64, f64)], song_length: f64) -> [u8; 3] {
+    // Total time spent in each (rounded) tempo
+    let mut bpm_durations = HashMap::<u16, f64>::new();
+
+    for (i, (pos, bpm)) in tempo_map.iter().enumerate() {
+        let end = match tempo_map.get(i + 1) {
+            Some((next_pos, _)) => *next_pos,
+            None => song_length.max(*pos + 1.0),
+        };
+
+        if end <= *pos {
+            // Ignore tempo overwritten by another at same position
+            continue;
+        }
+
+        let bpm = bpm.round().clamp(1.0, 999.0) as u16;
+        *bpm_durations.entry(bpm).or_default() += end - pos;
+    }
+
+    let common_bpm = bpm_durations
+        .iter()
+        .max_by(|(a_bpm, a_dur), (b_bpm, b_dur)| a_dur
+            .total_cmp(b_dur)
+            .then(b_bpm.cmp(a_bpm)))
+        .map(|(bpm, _)| *bpm);
+
+    match common_bpm {
+        Some(common_bpm) => [
+            get_bpm_tier(*bpm_durations.keys().min().unwrap()),
+            get_bpm_tier(common_bpm),
+            get_bpm_tier(*bpm_durations.keys().max().unwrap()),
+        ],
+        None => SongRatings::default().bpms,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ini_difficulty_in_game_range() {
+        assert_eq!([1, 1, 1, 1], get_difficulty_ratings_from_ini(0));
+        assert_eq!([1, 1, 1, 2], get_difficulty_ratings_from_ini(2));
+        assert_eq!([1, 1, 2, 3], get_difficulty_ratings_from_ini(4));
+        assert_eq!([1, 2, 3, 4], get_difficulty_ratings_from_ini(6));
+        assert_eq!([1, 2, 3, 4], get_difficulty_ratings_from_ini(9));
+    }
+
+    #[test]
+    fn bpm_tiers_from_tempo_map() {
+        assert_eq!(1, get_bpm_tier(80));
+        assert_eq!(2, get_bpm_tier(100));
+        assert_eq!(3, get_bpm_tier(140));
+        assert_eq!(3, get_bpm_tier(999));
+
+        // 90bpm intro, mostly 120bpm, 160bpm ending
+        let tempo_map = [(0.0, 90.0), (10_000.0, 120.0), (100_000.0, 160.0)];
+        assert_eq!([1, 2, 3], get_bpm_tiers(&tempo_map, 110_000.0));
+
+        assert_eq!(SongRatings::default().bpms, get_bpm_tiers(&[], 0.0));
+    }
+}
@@ -0,0 +1,390 @@
+use crate::xml::*;
+use std::fs::read_to_string;
+use std::path::Path;
+use std::str::FromStr;
+
+impl XmlFile {
+    pub fn from_path<T: AsRef<Path>>(xml_path: T) -> Result<XmlFile, XmlParseError> {
+        let text = read_to_string(xml_path)
+            .map_err(|err| XmlParseError::CantOpenXmlFile {
+                text: err.to_string(),
+            })?;
+
+        XmlFile::from_str(&text)
+    }
+}
+
+impl FromStr for XmlFile {
+    type Err = XmlParseError;
+
+    fn from_str(text: &str) -> Result<XmlFile, XmlParseError> {
+        // Ignore byte order mark if present
+        let text = text.trim_start_matches('\u{feff}');
+
+        let (root_name, root_text) = get_child_elements(text)
+            .into_iter()
+            .next()
+            .ok_or(XmlParseError::MissingRootElement)?;
+
+        let track = match root_name {
+            "beats" => parse_beats(root_text)?,
+            "lyrics" => parse_lyrics(root_text)?,
+            "data" => parse_metadata(root_text),
+            _ => return Err(XmlParseError::UnsupportedRootElement {
+                name: root_name.to_string(),
+            }),
+        };
+
+        Ok(XmlFile {
+            track,
+        })
+    }
+}
+
+fn parse_beats(text: &str) -> Result<XmlTrack, XmlParseError> {
+    let mut beats = Vec::new();
+
+    // Note name is combination of frets + modifiers (i.e. left_down_float_quick_spinner_)
+    for (note_name, note_text) in get_child_elements(text) {
+        let pos = match get_child_element(note_text, "target") {
+            Some(target) => parse_time("target", target)?,
+            None => continue,
+        };
+
+        let length = match get_child_element(note_text, "end") {
+            Some(end) => parse_time("end", end)?.saturating_sub(pos),
+            None => 0,
+        };
+
+        let mut beat = BeatEvent::default(pos, length);
+
+        for part in note_name.split('_') {
+            match part {
+                "left" => beat.green = true,
+                "down" => beat.red = true,
+                "up" => beat.yellow = true,
+                "right" => beat.blue = true,
+                "five" => beat.orange = true,
+                "quick" => beat.tap = true,
+                "spinner" => beat.star_power = true,
+                _ => continue, // "float" is implied by end time
+            }
+        }
+
+        beats.push(beat);
+    }
+
+    Ok(XmlTrack::GuitarBass(beats))
+}
+
+fn parse_lyrics(text: &str) -> Result<XmlTrack, XmlParseError> {
+    let mut lyrics = Vec::new();
+    let mut current_lyric: Option<LyricEvent> = None;
+
+    // Lyrics are stored as show/text/remove triplets
+    for (tag_name, value) in get_child_elements(text) {
+        match (tag_name, &mut current_lyric) {
+            ("show", _) => {
+                current_lyric = Some(LyricEvent {
+                    pos: parse_time(tag_name, value)?,
+                    length: 0,
+                    text: String::new(),
+                });
+            },
+            ("text", Some(lyric)) => lyric.text = unescape_xml_text(value),
+            ("remove", Some(lyric)) => {
+                lyric.length = parse_time(tag_name, value)?.saturating_sub(lyric.pos);
+                lyrics.push(current_lyric.take().unwrap());
+            },
+            _ => continue,
+        }
+    }
+
+    Ok(XmlTrack::Vocals(lyrics))
+}
+
+fn parse_metadata(text: &str) -> XmlTrack {
+    let albums = get_child_elements(get_child_element(text, "albumNames").unwrap_or_default())
+        .into_iter()
+        .map(|(_, album)| unescape_xml_text(album))
+        .collect();
+
+    let mut pack_id = None;
+    let mut songs = Vec::new();
+
+    for (tag_name, song_text) in get_child_elements(get_child_element(text, "tracks").unwrap_or_default()) {
+        let song_id = match tag_name
+            .strip_prefix("song_")
+            .and_then(|id| id.parse::<u16>().ok()) {
+            Some(id) => id,
+            None => continue,
+        };
+
+        // Pack id is first 2 digits of full song id (i.e. GPM04000.dpo)
+        if pack_id.is_none() {
+            pack_id = get_child_element(song_text, "wave")
+                .and_then(|wave| wave.trim().strip_prefix("GPM"))
+                .and_then(|wave| wave.get(..2))
+                .and_then(|id| id.parse::<u8>().ok());
+        }
+
+        songs.push(XmlSong {
+            song_id,
+            artist: unescape_xml_text(get_child_element(song_text, "artist").unwrap_or_default()),
+            title: unescape_xml_text(get_child_element(song_text, "title").unwrap_or_default()),
+            album_idx: get_child_element(song_text, "albumID")
+                .and_then(|id| id.trim().parse().ok())
+                .unwrap_or_default(),
+            ratings: parse_song_ratings(song_text),
+        });
+    }
+
+    XmlTrack::Metadata {
+        name: unescape_xml_text(get_child_element(text, "exp_title").unwrap_or_default()),
+        pack_id: pack_id.unwrap_or_default(),
+        albums,
+        songs,
+    }
+}
+
+fn parse_song_ratings(song_text: &str) -> SongRatings {
+    let mut ratings = SongRatings::default();
+
+    let difficulty_tags = ["difficulty_easy", "difficulty_medium", "difficulty_hard", "difficulty_expert"];
+    for (rating, tag_name) in ratings.difficulties.iter_mut().zip(difficulty_tags.iter()) {
+        if let Some(value) = get_child_element(song_text, tag_name).and_then(|v| v.trim().parse::<u8>().ok()) {
+            *rating = value.clamp(MIN_DIFFICULTY_RATING, MAX_DIFFICULTY_RATING);
+        }
+    }
+
+    let bpm_tags = ["bpm_easy", "bpm_medium", "bpm_hard"];
+    for (bpm, tag_name) in ratings.bpms.iter_mut().zip(bpm_tags.iter()) {
+        if let Some(value) = get_child_element(song_text, tag_name).and_then(|v| v.trim().parse::<u8>().ok()) {
+            *bpm = value.clamp(MIN_BPM_TIER, MAX_BPM_TIER);
+        }
+    }
+
+    ratings
+}
+
+fn parse_time(tag_name: &str, value: &str) -> Result<u64, XmlParseError> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| XmlParseError::InvalidTimeValue {
+            tag_name: tag_name.to_string(),
+            value: value.to_string(),
+        })
+}
+
+// Returns name + inner text for each top level element
+fn get_child_elements(text: &str) -> Vec<(&str, &str)> {
+    let mut elements = Vec::new();
+    let mut remaining = text;
+
+    while let Some(start_idx) = remaining.find('<') {
+        remaining = &remaining[(start_idx + 1)..];
+
+        let tag_end_idx = match remaining.find('>') {
+            Some(idx) => idx,
+            None => break,
+        };
+
+        let tag = &remaining[..tag_end_idx];
+        remaining = &remaining[(tag_end_idx + 1)..];
+
+        // Skip declarations, comments and stray end tags
+        if tag.starts_with('?') || tag.starts_with('!') || tag.starts_with('/') {
+            continue;
+        }
+
+        let tag_name = tag
+            .trim_end_matches('/')
+            .split_whitespace()
+            .next()
+            .unwrap_or_default();
+
+        if tag.ends_with('/') {
+            elements.push((tag_name, ""));
+            continue;
+        }
+
+        let end_tag = format!("</{}>", tag_name);
+        match remaining.find(&end_tag) {
+            Some(end_idx) => {
+                elements.push((tag_name, &remaining[..end_idx]));
+                remaining = &remaining[(end_idx + end_tag.len())..];
+            },
+            None => break,
+        }
+    }
+
+    elements
+}
+
+fn get_child_element<'a>(text: &'a str, name: &str) -> Option<&'a str> {
+    get_child_elements(text)
+        .into_iter()
+        .find(|(tag_name, _)| tag_name.eq(&name))
+        .map(|(_, value)| value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(xml_file: &XmlFile) -> XmlFile {
+        let mut data = Vec::new();
+        xml_file.write(&mut data).unwrap();
+
+        XmlFile::from_str(&String::from_utf8(data).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn parse_beat_note_name() {
+        let text = "<?xml version='1.1'?>
+<beats>
+\t<left_down_float_quick_spinner_>
+\t\t<show>0</show>
+\t\t<target>1000</target>
+\t\t<end>1500</end>
+\t</left_down_float_quick_spinner_>
+\t<five_>
+\t\t<show>0</show>
+\t\t<target>2000</target>
+\t</five_>
+</beats>";
+
+        let mut expected_chord = BeatEvent::default(1000, 500);
+        expected_chord.green = true;
+        expected_chord.red = true;
+        expected_chord.tap = true;
+        expected_chord.star_power = true;
+
+        let mut expected_orange = BeatEvent::default(2000, 0);
+        expected_orange.orange = true;
+
+        let xml_file = XmlFile::from_str(text).unwrap();
+        assert_eq!(XmlTrack::GuitarBass(vec![expected_chord, expected_orange]), xml_file.track);
+    }
+
+    #[test]
+    fn round_trip_beats() {
+        let mut beats = Vec::new();
+
+        for i in 0..32u64 {
+            let mut beat = BeatEvent::default(i * 250, (i % 3) * 100);
+            beat.green = i & 1 != 0;
+            beat.red = i & 2 != 0;
+            beat.yellow = i & 4 != 0;
+            beat.blue = i & 8 != 0;
+            beat.orange = i & 16 != 0 || i == 0;
+            beat.tap = i % 5 == 0;
+            beat.star_power = i % 7 == 0;
+
+            beats.push(beat);
+        }
+
+        let xml_file = XmlFile {
+            track: XmlTrack::GuitarBass(beats),
+        };
+
+        assert_eq!(xml_file, round_trip(&xml_file));
+    }
+
+    #[test]
+    fn round_trip_lyrics() {
+        let xml_file = XmlFile {
+            track: XmlTrack::Vocals(vec![
+                LyricEvent {
+                    pos: 500,
+                    length: 1000,
+                    text: String::from("Hello world"),
+                },
+                LyricEvent {
+                    pos: 3000,
+                    length: 250,
+                    text: String::from("Amen"),
+                },
+            ]),
+        };
+
+        assert_eq!(xml_file, round_trip(&xml_file));
+    }
+
+    #[test]
+    fn round_trip_metadata() {
+        let xml_file = XmlFile {
+            track: XmlTrack::Metadata {
+                name: String::from("Custom Song Pack"),
+                pack_id: 5,
+                albums: vec![String::from("First Album"), String::from("Second Album")],
+                songs: vec![
+                    XmlSong {
+                        song_id: 0,
+                        artist: String::from("Artist A"),
+                        title: String::from("Song A"),
+                        album_idx: 1,
+                        ratings: SongRatings {
+                            difficulties: [1, 2, 3, 4],
+                            bpms: [1, 2, 3],
+                        },
+                    },
+                    XmlSong {
+                        song_id: 1,
+                        artist: String::from("Artist B"),
+                        title: String::from("Song B"),
+                        album_idx: 0,
+                        ratings: SongRatings::default(),
+                    },
+                ],
+            },
+        };
+
+        assert_eq!(xml_file, round_trip(&xml_file));
+    }
+
+    #[test]
+    fn round_trip_escaped_text() {
+        let xml_file = XmlFile {
+            track: XmlTrack::Vocals(vec![
+                LyricEvent {
+                    pos: 0,
+                    length: 100,
+                    text: String::from("Salt & Light <\"Live\"> 'n' more"),
+                },
+            ]),
+        };
+
+        assert_eq!(xml_file, round_trip(&xml_file));
+    }
+
+    #[test]
+    fn replace_unsupported_characters() {
+        let xml_file = XmlFile {
+            track: XmlTrack::Vocals(vec![
+                LyricEvent {
+                    pos: 0,
+                    length: 100,
+                    text: String::from("Beyonc\u{e9} \u{2013} \u{201c}D\u{e9}j\u{e0} Vu\u{201d}\u{2026} \u{266a}"),
+                },
+            ]),
+        };
+
+        let expected_track = XmlTrack::Vocals(vec![
+            LyricEvent {
+                pos: 0,
+                length: 100,
+                text: String::from("Beyonce - \"Deja Vu\"... ?"),
+            },
+        ]);
+
+        assert_eq!(expected_track, round_trip(&xml_file).track);
+    }
+
+    #[test]
+    fn unsupported_root_element() {
+        let res = XmlFile::from_str("<?xml version='1.1'?>\n<notes></notes>");
+        assert!(matches!(res, Err(XmlParseError::UnsupportedRootElement { .. })));
+    }
+}
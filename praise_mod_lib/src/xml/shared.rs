@@ -1,3 +1,5 @@
+use crate::xml::SongRatings;
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum XmlTrackType {
     Guitar,
@@ -13,7 +15,7 @@ pub enum XmlTrackDifficulty {
     Expert,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct BeatEvent {
     pub pos: u64,
     pub length: u64,
@@ -84,22 +86,23 @@ impl BeatEvent {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct LyricEvent {
     pub pos: u64,
     pub length: u64,
     pub text: String,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct XmlSong {
     pub song_id: u16,
     pub artist: String,
     pub title: String,
     pub album_idx: u16,
+    pub ratings: SongRatings,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum XmlTrack {
     GuitarBass(Vec<BeatEvent>),
     Vocals(Vec<LyricEvent>),
@@ -5,7 +5,7 @@ use crate::xml::*;
 pub struct XmlSongMetaBuilder {
     pub pack_name: String,
     pub pack_id: u8,
-    song_metas: Vec<(SongMeta, u16)>,
+    song_metas: Vec<(SongMeta, u16, SongRatings)>,
 }
 
 impl XmlSongMetaBuilder {
@@ -17,15 +17,15 @@ impl XmlSongMetaBuilder {
         }
     }
 
-    pub fn add_song(&mut self, song_meta: &SongMeta, id: u16) {
-        self.song_metas.push((song_meta.clone(), id)); // Assume ids won't be duplicated
+    pub fn add_song(&mut self, song_meta: &SongMeta, id: u16, ratings: SongRatings) {
+        self.song_metas.push((song_meta.clone(), id, ratings)); // Assume ids won't be duplicated
     }
 
     pub fn to_xml_meta(&self) -> XmlFile {
         // Get unique album names
         let mut albums = self.song_metas
             .iter()
-            .map(|(meta, _)| meta.album.as_str())
+            .map(|(meta, _, _)| meta.album.as_str())
             .collect::<HashSet<&str>>()
             .into_iter()
             .collect::<Vec<&str>>();
@@ -40,14 +40,15 @@ impl XmlSongMetaBuilder {
 
         let song_data = self.song_metas
             .iter()
-            .map(|(meta, id)| XmlSong {
+            .map(|(meta, id, ratings)| XmlSong {
                 song_id: *id,
                 artist: meta.artist.to_owned(),
                 title: meta.name.to_owned(),
-                album_idx: match albums_by_id.get(meta.artist.as_str()) {
+                album_idx: match albums_by_id.get(meta.album.as_str()) {
                     Some(id) => *id,
                     None => 0,
                 },
+                ratings: *ratings,
             })
             .collect();
 
@@ -0,0 +1,101 @@
+// Game font only has glyphs for printable ascii
+const GAME_FONT_RANGE: std::ops::RangeInclusive<char> = ' '..='~';
+
+const GAME_FONT_FALLBACK: &str = "?";
+
+pub fn escape_xml_text(text: &str) -> String {
+    let mut escaped_text = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped_text += "&amp;",
+            '<' => escaped_text += "&lt;",
+            '>' => escaped_text += "&gt;",
+            '"' => escaped_text += "&quot;",
+            '\'' => escaped_text += "&apos;",
+            _ => escaped_text.push(c),
+        }
+    }
+
+    escaped_text
+}
+
+pub fn unescape_xml_text(text: &str) -> String {
+    text
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+pub fn to_game_font_text(text: &str) -> String {
+    text
+        .chars()
+        .map(|c| match c {
+            c if GAME_FONT_RANGE.contains(&c) => c.to_string(),
+            c => get_game_font_replacement(c)
+                .unwrap_or(GAME_FONT_FALLBACK)
+                .to_string(),
+        })
+        .collect()
+}
+
+pub fn get_game_font_replacement(c: char) -> Option<&'static str> {
+    let replacement = match c {
+        // Whitespace
+        '\t' | '\n' | '\r' | '\u{a0}' | '\u{2002}'..='\u{200a}' | '\u{3000}' => " ",
+        '\u{200b}' | '\u{feff}' => "",
+        // Punctuation
+        '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{2032}' | '\u{b4}' => "'",
+        '\u{201c}' | '\u{201d}' | '\u{201e}' | '\u{2033}' | '\u{ab}' | '\u{bb}' => "\"",
+        '\u{2010}'..='\u{2015}' | '\u{2212}' => "-",
+        '\u{2026}' => "...",
+        '\u{2022}' | '\u{b7}' => "*",
+        '\u{a1}' => "!",
+        '\u{bf}' => "?",
+        '\u{d7}' => "x",
+        '\u{a9}' => "(c)",
+        '\u{ae}' => "(r)",
+        '\u{2122}' => "(tm)",
+        // Latin letters
+        '\u{c0}'..='\u{c5}' | '\u{100}' | '\u{102}' | '\u{104}' => "A",
+        '\u{e0}'..='\u{e5}' | '\u{101}' | '\u{103}' | '\u{105}' => "a",
+        '\u{c6}' => "AE",
+        '\u{e6}' => "ae",
+        '\u{c7}' | '\u{106}' | '\u{10c}' => "C",
+        '\u{e7}' | '\u{107}' | '\u{10d}' => "c",
+        '\u{d0}' | '\u{10e}' | '\u{110}' => "D",
+        '\u{f0}' | '\u{10f}' | '\u{111}' => "d",
+        '\u{c8}'..='\u{cb}' | '\u{112}' | '\u{118}' | '\u{11a}' => "E",
+        '\u{e8}'..='\u{eb}' | '\u{113}' | '\u{119}' | '\u{11b}' => "e",
+        '\u{11e}' => "G",
+        '\u{11f}' => "g",
+        '\u{cc}'..='\u{cf}' | '\u{130}' => "I",
+        '\u{ec}'..='\u{ef}' | '\u{131}' => "i",
+        '\u{141}' => "L",
+        '\u{142}' => "l",
+        '\u{d1}' | '\u{143}' | '\u{147}' => "N",
+        '\u{f1}' | '\u{144}' | '\u{148}' => "n",
+        '\u{d2}'..='\u{d6}' | '\u{d8}' | '\u{150}' => "O",
+        '\u{f2}'..='\u{f6}' | '\u{f8}' | '\u{151}' => "o",
+        '\u{152}' => "OE",
+        '\u{153}' => "oe",
+        '\u{158}' => "R",
+        '\u{159}' => "r",
+        '\u{15a}' | '\u{15e}' | '\u{160}' => "S",
+        '\u{15b}' | '\u{15f}' | '\u{161}' => "s",
+        '\u{df}' => "ss",
+        '\u{162}' | '\u{164}' => "T",
+        '\u{163}' | '\u{165}' => "t",
+        '\u{d9}'..='\u{dc}' | '\u{16e}' | '\u{170}' => "U",
+        '\u{f9}'..='\u{fc}' | '\u{16f}' | '\u{171}' => "u",
+        '\u{dd}' | '\u{178}' => "Y",
+        '\u{fd}' | '\u{ff}' => "y",
+        '\u{179}' | '\u{17b}' | '\u{17d}' => "Z",
+        '\u{17a}' | '\u{17c}' | '\u{17e}' => "z",
+        _ => return None,
+    };
+
+    Some(replacement)
+}
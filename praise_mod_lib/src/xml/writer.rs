@@ -3,12 +3,16 @@ use std::io::{Write, BufReader, BufRead, Error};
 use crate::midi::*;
 use log::{info, warn};
 use std::path::{Path, PathBuf};
+use crate::PraiseError;
 use crate::xml::*;
 
 impl XmlFile {
-    pub fn write_to_file(&self, xml_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn write_to_file(&self, xml_path: &Path) -> Result<(), PraiseError> {
         let mut xml_file = File::create(xml_path)?;
+        self.write(&mut xml_file)
+    }
 
+    pub fn write<T: Write>(&self, xml_file: &mut T) -> Result<(), PraiseError> {
         if let XmlTrack::GuitarBass(beats) = &self.track {
             writeln!(xml_file, "<?xml version='1.1'?>")?;
             writeln!(xml_file, "<beats>")?;
@@ -43,7 +47,7 @@ impl XmlFile {
             // Iterate over lyrics
             for lyric in lyrics.iter() {
                 writeln!(xml_file, "\t<show>{}</show>", lyric.pos)?;
-                writeln!(xml_file, "\t<text>{}</text>", lyric.text)?;
+                writeln!(xml_file, "\t<text>{}</text>", format_xml_text(&lyric.text))?;
                 writeln!(xml_file, "\t<remove>{}</remove>", lyric.pos + lyric.length)?;
             }
 
@@ -52,13 +56,13 @@ impl XmlFile {
             writeln!(xml_file, "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\" ?>")?;
             writeln!(xml_file, "<data>")?;
 
-            writeln!(xml_file, "\t<exp_title>{}</exp_title>", name)?;
+            writeln!(xml_file, "\t<exp_title>{}</exp_title>", format_xml_text(name))?;
             writeln!(xml_file, "\t<difficultyIcon>191042.dpa</difficultyIcon>")?;
 
             // Write album names
             writeln!(xml_file, "\t<albumNames>")?;
             for (i, album) in albums.iter().enumerate() {
-                writeln!(xml_file, "\t\t<Name{0}>{1}</Name{0}>", i, album)?;
+                writeln!(xml_file, "\t\t<Name{0}>{1}</Name{0}>", i, format_xml_text(album))?;
             }
             writeln!(xml_file, "\t</albumNames>")?;
 
@@ -69,16 +73,16 @@ impl XmlFile {
 
                 writeln!(xml_file, "\t\t<song_{:03}>", song.song_id)?;
 
-                writeln!(xml_file, "\t\t\t<artist>{}</artist>", song.artist)?;
-                writeln!(xml_file, "\t\t\t<title>{}</title>", song.title)?;
+                writeln!(xml_file, "\t\t\t<artist>{}</artist>", format_xml_text(&song.artist))?;
+                writeln!(xml_file, "\t\t\t<title>{}</title>", format_xml_text(&song.title))?;
                 writeln!(xml_file, "\t\t\t<short_title></short_title>")?;
-                writeln!(xml_file, "\t\t\t<difficulty_easy>1</difficulty_easy>")?;
-                writeln!(xml_file, "\t\t\t<difficulty_medium>2</difficulty_medium>")?;
-                writeln!(xml_file, "\t\t\t<difficulty_hard>3</difficulty_hard>")?;
-                writeln!(xml_file, "\t\t\t<difficulty_expert>4</difficulty_expert>")?;
-                writeln!(xml_file, "\t\t\t<bpm_easy>003</bpm_easy>")?;
-                writeln!(xml_file, "\t\t\t<bpm_medium>002</bpm_medium>")?;
-                writeln!(xml_file, "\t\t\t<bpm_hard>003</bpm_hard>")?;
+                writeln!(xml_file, "\t\t\t<difficulty_easy>{}</difficulty_easy>", song.ratings.difficulties[0])?;
+                writeln!(xml_file, "\t\t\t<difficulty_medium>{}</difficulty_medium>", song.ratings.difficulties[1])?;
+                writeln!(xml_file, "\t\t\t<difficulty_hard>{}</difficulty_hard>", song.ratings.difficulties[2])?;
+                writeln!(xml_file, "\t\t\t<difficulty_expert>{}</difficulty_expert>", song.ratings.difficulties[3])?;
+                writeln!(xml_file, "\t\t\t<bpm_easy>{:03}</bpm_easy>", song.ratings.bpms[0])?;
+                writeln!(xml_file, "\t\t\t<bpm_medium>{:03}</bpm_medium>", song.ratings.bpms[1])?;
+                writeln!(xml_file, "\t\t\t<bpm_hard>{:03}</bpm_hard>", song.ratings.bpms[2])?;
                 writeln!(xml_file, "\t\t\t<albumID>{}</albumID>", song.album_idx)?;
                 writeln!(xml_file, "\t\t\t<albumImage>CD_197237.bmp</albumImage>")?;
                 writeln!(xml_file, "\t\t\t<cd_website></cd_website>")?;
@@ -96,4 +100,9 @@ impl XmlFile {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+fn format_xml_text(text: &str) -> String {
+    // Replace unsupported characters before escaping
+    escape_xml_text(&to_game_font_text(text))
+}
@@ -4,12 +4,24 @@ use std::io::{Write, BufReader, BufRead, Error};
 use std::path::{Path, PathBuf};
 use crate::xml::*;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct XmlFile {
     pub track: XmlTrack,
 }
 
 impl XmlFile {
+    pub fn offset_events(&mut self, offset_ms: u64) {
+        match &mut self.track {
+            XmlTrack::GuitarBass(beats) => beats
+                .iter_mut()
+                .for_each(|b| b.pos += offset_ms),
+            XmlTrack::Vocals(lyrics) => lyrics
+                .iter_mut()
+                .for_each(|l| l.pos += offset_ms),
+            XmlTrack::Metadata { .. } => {},
+        }
+    }
+
     pub(crate) fn update_fret_beat_event(note: &mut BeatEvent, length: u64, index: u8, sp: bool, tap: bool) {
         // Update length if small
         if note.length < length {